@@ -1,60 +1,76 @@
 //! --- Day 1: Report Repair ---
 
-use crate::solver::Solver;
+use crate::{
+    ksum::{k_sums, Strategy},
+    solver::Solver,
+};
 use std::io::{self, BufRead, BufReader};
 
 /// https://adventofcode.com/2020/day/1
-pub struct Day01;
+pub struct Day01 {
+    /// Print every set of `k` entries that add up to this (defaults to 2020).
+    pub target: Option<i64>,
+    /// Number of entries that must add up to `target` (defaults to 2).
+    pub k: Option<usize>,
+    /// How to find the entries: "two-pointer" (the default) or "meet-in-the-middle".
+    pub strategy: Option<Strategy>,
+}
+
+const TARGET_SUM: i64 = 2020;
+
+/// Returns the product of the first `k` entries that add up to `TARGET_SUM`.
+fn product_of_first(entries: &[i64], k: usize) -> i64 {
+    let k_sums = k_sums(entries, k, TARGET_SUM, Strategy::TwoPointer);
 
-const TARGET_SUM: usize = 2020;
+    k_sums[0].iter().map(|&i| entries[i]).product()
+}
 
 impl Solver for Day01 {
-    type Input = Vec<usize>;
-    type Output1 = usize;
-    type Output2 = usize;
+    type Input = Vec<i64>;
+    type Output1 = i64;
+    type Output2 = i64;
 
+    /// Find the two entries that sum to 2020; what do you get if you multiply them together?
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        // @Note: while the natural solution would be to use a HashSet,
-        // using a Vec with less than 2048 positions will do just fine.
-        let mut has_complement = [false; TARGET_SUM + 1];
-
-        for &entry in input {
-            if has_complement[entry] {
-                return entry * (TARGET_SUM - entry);
-            }
-            has_complement[TARGET_SUM - entry] = true;
-        }
-
-        unreachable!()
+        product_of_first(input, 2)
     }
 
+    /// What is the product of the three entries that sum to 2020?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        // @Note: same idea as above.. but O(n^2) instead of O(n).
-        let mut has_complement: [bool; TARGET_SUM + 1];
-
-        for current_target in input.iter().map(|&entry| TARGET_SUM - entry) {
-            has_complement = [false; TARGET_SUM + 1];
-
-            for &entry in input.iter().filter(|&&entry| entry <= current_target) {
-                if has_complement[entry] {
-                    return entry * (current_target - entry) * (TARGET_SUM - current_target);
-                }
-                has_complement[current_target - entry] = true;
-            }
-        }
-
-        unreachable!()
+        product_of_first(input, 3)
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
-        let input = BufReader::new(r)
+        BufReader::new(r)
             .lines()
             .flatten()
             .map(|line| line.parse().unwrap())
-            .collect::<Vec<usize>>();
+            .collect()
+    }
 
-        assert!(input.iter().all(|&entry| entry <= TARGET_SUM));
+    fn report(&self, input: &Self::Input) {
+        if self.target.is_none() && self.k.is_none() && self.strategy.is_none() {
+            return;
+        }
+
+        let (target, k) = (self.target.unwrap_or(TARGET_SUM), self.k.unwrap_or(2));
+        let strategy = self.strategy.unwrap_or(Strategy::TwoPointer);
 
-        input
+        let k_sums = k_sums(input, k, target, strategy);
+        for indices in &k_sums {
+            let entries = indices.iter().map(|&i| input[i]).collect::<Vec<_>>();
+            println!(
+                "Lines {:?}: {:?} (product {})",
+                indices.iter().map(|i| i + 1).collect::<Vec<_>>(),
+                entries,
+                entries.iter().product::<i64>()
+            );
+        }
+        println!(
+            "{} sets of {} entries add up to {}",
+            k_sums.len(),
+            k,
+            target
+        );
     }
 }
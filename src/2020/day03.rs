@@ -1,10 +1,22 @@
 //! --- Day 3: Toboggan Trajectory ---
 
 use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use std::{
+    fmt::{self, Display},
+    io::{self, BufRead, BufReader},
+};
 
 /// https://adventofcode.com/2020/day/3
-pub struct Day03;
+pub struct Day03 {
+    /// Print the number of trees encountered on each of these slopes (and their product),
+    /// given as "right,down" pairs separated by semicolons, e.g. "3,1;1,2".
+    pub slopes: Option<String>,
+    /// Find the slope with the fewest trees, among those going right and down at most
+    /// this many squares at a time.
+    pub search: Option<usize>,
+    /// Print the grid with the path taken on this slope (e.g. "3,1").
+    pub render: Option<String>,
+}
 
 enum GridCell {
     Open,
@@ -16,34 +28,51 @@ pub struct Grid {
     height: usize,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 struct Coord(usize, usize); // (x, y)
 #[derive(Copy, Clone)]
 struct Slope(usize, usize); // (right, down)
 
+/// Grid with the positions on a slope marked as O (if open) or X (if a tree).
+struct GridWithPath<'a> {
+    grid: &'a Grid,
+    slope: Slope,
+}
+
 const START: Coord = Coord(0, 0); // top-left
 const SLOPE: Slope = Slope(3, 1); // right 3, down 1
 
+impl Slope {
+    /// Parses a "right,down" pair, where the slope must go down.
+    fn parse(slope: &str) -> Self {
+        let parsed = slope.split_once(',').and_then(|(right, down)| {
+            Some((right.trim().parse().ok()?, down.trim().parse().ok()?))
+        });
+        match parsed {
+            Some((right, down)) if down > 0 => Slope(right, down),
+            Some(_) => panic!("slope doesn't go down: {}", slope),
+            None => panic!("invalid slope: {}", slope),
+        }
+    }
+}
+
 impl Grid {
     /// Returns the coordinates of the cells that will be checked in the grid,
     /// given a starting position `start` and the `slope`.
-    fn positions_on_slope(&self, start: Coord, slope: Slope) -> Vec<Coord> {
+    fn positions_on_slope(&self, start: Coord, slope: Slope) -> impl Iterator<Item = Coord> {
         assert!(slope.1 > 0);
 
-        (start.1..=self.height)
+        let (width, right) = (self.width, slope.0);
+        (start.1..self.height)
             .step_by(slope.1)
-            .fold((vec![], start.0), |(mut positions, x), y| {
-                positions.push(Coord(x, y));
-                (positions, (x + slope.0) % self.width)
-            })
-            .0
+            .enumerate()
+            .map(move |(i, y)| Coord((start.0 + i * right) % width, y))
     }
 
     /// Returns the number of grid cells, given by `positions`, that are trees.
-    fn count_trees_in(&self, positions: &[Coord]) -> usize {
+    fn count_trees_in(&self, positions: impl Iterator<Item = Coord>) -> usize {
         positions
-            .iter()
-            .filter(|&&Coord(x, y)| {
+            .filter(|&Coord(x, y)| {
                 matches!(
                     self.cells.get(y).and_then(|row| row.get(x)),
                     Some(GridCell::Tree)
@@ -51,6 +80,27 @@ impl Grid {
             })
             .count()
     }
+
+    fn trees_on_slope(&self, slope: Slope) -> usize {
+        self.count_trees_in(self.positions_on_slope(START, slope))
+    }
+
+    /// Returns the slope with the fewest trees (and how many), among those going
+    /// right at most `bound` squares and down at least 1 and at most `bound` squares
+    /// at a time.
+    fn fewest_trees(&self, bound: usize) -> (Slope, usize) {
+        assert!(bound > 0, "the search bound must be at least 1");
+
+        (1..=bound)
+            .flat_map(|down| (0..=bound).map(move |right| Slope(right, down)))
+            .map(|slope| (slope, self.trees_on_slope(slope)))
+            .min_by_key(|&(_, trees)| trees)
+            .unwrap()
+    }
+
+    fn with_path(&self, slope: Slope) -> GridWithPath<'_> {
+        GridWithPath { grid: self, slope }
+    }
 }
 
 impl Solver for Day03 {
@@ -59,7 +109,7 @@ impl Solver for Day03 {
     type Output2 = usize;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        input.count_trees_in(&input.positions_on_slope(START, SLOPE))
+        input.trees_on_slope(SLOPE)
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
@@ -71,7 +121,7 @@ impl Solver for Day03 {
             Slope(1, 2), // right 1, down 2
         ]
         .iter()
-        .map(|&slope| input.count_trees_in(&input.positions_on_slope(START, slope)))
+        .map(|&slope| input.trees_on_slope(slope))
         .product()
     }
 
@@ -97,6 +147,30 @@ impl Solver for Day03 {
             height,
         }
     }
+
+    fn report(&self, input: &Self::Input) {
+        if let Some(slopes) = &self.slopes {
+            let mut product = 1;
+            for slope in slopes.split(';').map(Slope::parse) {
+                let trees = input.trees_on_slope(slope);
+                println!("Right {}, down {}: {} trees", slope.0, slope.1, trees);
+                product *= trees;
+            }
+            println!("Product: {}", product);
+        }
+
+        if let Some(bound) = self.search {
+            let (slope, trees) = input.fewest_trees(bound);
+            println!(
+                "Fewest trees: {} (right {}, down {})",
+                trees, slope.0, slope.1
+            );
+        }
+
+        if let Some(slope) = &self.render {
+            print!("{}", input.with_path(Slope::parse(slope)));
+        }
+    }
 }
 
 impl GridCell {
@@ -109,11 +183,8 @@ impl GridCell {
     }
 }
 
-/*
-use std::fmt::Display;
-
 impl Display for GridCell {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             GridCell::Open => write!(f, "."),
             GridCell::Tree => write!(f, "#"),
@@ -122,7 +193,7 @@ impl Display for GridCell {
 }
 
 impl Display for Grid {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for row in &self.cells {
             for cell in row {
                 write!(f, "{}", cell)?;
@@ -132,4 +203,21 @@ impl Display for Grid {
         Ok(())
     }
 }
-*/
+
+impl Display for GridWithPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut path = self.grid.positions_on_slope(START, self.slope).peekable();
+
+        for (y, row) in self.grid.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                match (cell, path.next_if_eq(&Coord(x, y))) {
+                    (GridCell::Open, Some(_)) => write!(f, "O")?,
+                    (GridCell::Tree, Some(_)) => write!(f, "X")?,
+                    (cell, None) => write!(f, "{}", cell)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -7,24 +7,224 @@ use std::{
 };
 
 /// https://adventofcode.com/2020/day/4
-pub struct Day04;
+pub struct Day04 {
+    /// Print which fields of each passport are missing or invalid, and why.
+    pub explain: bool,
+    /// Name of the rule set used to count valid passports and explain them (defaults
+    /// to "strict"). The answers always follow the puzzle's own rules.
+    pub rules: Option<String>,
+}
+
+/// Sequence of `key:value` pairs.
+pub struct Passport(Vec<(String, String)>);
+
+/// Rule that the value of a field must follow.
+pub enum Rule {
+    /// Any value.
+    Any,
+    /// A number with exactly `digits` digits, within `range`.
+    Number {
+        digits: usize,
+        range: RangeInclusive<usize>,
+    },
+    /// A number followed by one of the units, each with its own valid range.
+    Measure(&'static [(&'static str, RangeInclusive<usize>)]),
+    /// A `prefix` followed by exactly `len` characters from `chars`.
+    Chars {
+        prefix: &'static str,
+        len: usize,
+        chars: &'static str,
+    },
+    /// Exactly one of the values.
+    OneOf(&'static [&'static str]),
+}
+
+pub struct Field {
+    key: &'static str,
+    required: bool,
+    rule: Rule,
+}
+
+const fn field(key: &'static str, required: bool, rule: Rule) -> Field {
+    Field {
+        key,
+        required,
+        rule,
+    }
+}
+
+/// Fields that passports can have (ignoring any other ones).
+pub struct RuleSet {
+    fields: &'static [Field],
+    /// Keys of the fields which are required, even though `fields` has them optional.
+    also_required: &'static [&'static str],
+}
+
+/// Only checks that all fields (except cid) are present.
+const PRESENCE_FIELDS: &[Field] = &[
+    field("byr", true, Rule::Any),
+    field("iyr", true, Rule::Any),
+    field("eyr", true, Rule::Any),
+    field("hgt", true, Rule::Any),
+    field("hcl", true, Rule::Any),
+    field("ecl", true, Rule::Any),
+    field("pid", true, Rule::Any),
+    field("cid", false, Rule::Any),
+];
+
+#[rustfmt::skip]
+const STRICT_FIELDS: &[Field] = &[
+    // byr (Birth Year) - four digits; at least 1920 and at most 2002.
+    field("byr", true, Rule::Number { digits: 4, range: 1920..=2002 }),
+
+    // iyr (Issue Year) - four digits; at least 2010 and at most 2020.
+    field("iyr", true, Rule::Number { digits: 4, range: 2010..=2020 }),
+
+    // eyr (Expiration Year) - four digits; at least 2020 and at most 2030.
+    field("eyr", true, Rule::Number { digits: 4, range: 2020..=2030 }),
+
+    // hgt (Height) - a number followed by either cm or in:
+    //   - If cm, the number must be at least 150 and at most 193.
+    //   - If in, the number must be at least 59 and at most 76.
+    field("hgt", true, Rule::Measure(&[("cm", 150..=193), ("in", 59..=76)])),
+
+    // hcl (Hair Color) - a # followed by exactly six characters 0-9 or a-f.
+    field("hcl", true, Rule::Chars { prefix: "#", len: 6, chars: "0123456789abcdef" }),
+
+    // ecl (Eye Color) - exactly one of: amb blu brn gry grn hzl oth.
+    field("ecl", true, Rule::OneOf(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"])),
+
+    // pid (Passport ID) - a nine-digit number, including leading zeroes.
+    field("pid", true, Rule::Chars { prefix: "", len: 9, chars: "0123456789" }),
+
+    // cid (Country ID) - ignored, missing or not.
+    field("cid", false, Rule::Any),
+];
+
+const PRESENCE: RuleSet = RuleSet {
+    fields: PRESENCE_FIELDS,
+    also_required: &[],
+};
+
+const STRICT: RuleSet = RuleSet {
+    fields: STRICT_FIELDS,
+    also_required: &[],
+};
+
+/// Same as `STRICT`, but without accepting North Pole Credentials (i.e. missing cid).
+const STRICT_CID: RuleSet = RuleSet {
+    fields: STRICT_FIELDS,
+    also_required: &["cid"],
+};
+
+/// Returns the rule set named `name`.
+fn rules(name: &str) -> Option<&'static RuleSet> {
+    match name {
+        "presence" => Some(&PRESENCE),
+        "strict" => Some(&STRICT),
+        "strict-cid" => Some(&STRICT_CID),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+pub struct Report {
+    /// Keys of the required fields which are missing.
+    missing: Vec<&'static str>,
+    /// Keys and values of the fields which are invalid, and why.
+    invalid: Vec<(String, String, String)>,
+}
+
+impl Report {
+    fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty()
+    }
+}
+
+impl Rule {
+    /// Returns why `value` doesn't follow this rule, if it doesn't.
+    fn check(&self, value: &str) -> Result<(), String> {
+        fn check_number(number: &str, range: &RangeInclusive<usize>) -> Result<(), String> {
+            match number.parse::<usize>() {
+                Ok(n) if range.contains(&n) => Ok(()),
+                Ok(n) => Err(format!("{} isn't within {:?}", n, range)),
+                Err(_) => Err(format!("{:?} isn't a number", number)),
+            }
+        }
+
+        match self {
+            Rule::Any => Ok(()),
+
+            Rule::Number { digits, range } => {
+                if value.len() != *digits {
+                    return Err(format!("expected {} digits", digits));
+                }
+                check_number(value, range)
+            }
+
+            Rule::Measure(units) => units
+                .iter()
+                .find(|(unit, _)| value.ends_with(unit))
+                .ok_or_else(|| {
+                    let units = units.iter().map(|(unit, _)| *unit).collect::<Vec<_>>();
+                    format!("expected a unit in {:?}", units)
+                })
+                .and_then(|(unit, range)| check_number(&value[..value.len() - unit.len()], range)),
+
+            Rule::Chars { prefix, len, chars } => match value.strip_prefix(prefix) {
+                Some(rest) if rest.len() == *len && rest.chars().all(|c| chars.contains(c)) => {
+                    Ok(())
+                }
+                _ if prefix.is_empty() => Err(format!("expected {} of {:?}", len, chars)),
+                _ => Err(format!("expected {:?} and {} of {:?}", prefix, len, chars)),
+            },
+
+            Rule::OneOf(values) => match values.contains(&value) {
+                true => Ok(()),
+                false => Err(format!("expected one of {:?}", values)),
+            },
+        }
+    }
+}
+
+impl Passport {
+    fn validate(&self, rules: &RuleSet) -> Report {
+        let mut report = Report::default();
+
+        for field in rules.fields {
+            let required = field.required || rules.also_required.contains(&field.key);
+            if required && !self.0.iter().any(|(key, _)| key == field.key) {
+                report.missing.push(field.key);
+            }
+        }
+
+        for (key, value) in &self.0 {
+            let field = rules.fields.iter().find(|field| field.key == key);
+            if let Some(Err(reason)) = field.map(|field| field.rule.check(value)) {
+                report.invalid.push((key.clone(), value.clone(), reason));
+            }
+        }
+
+        report
+    }
+}
 
 impl Solver for Day04 {
-    type Input = Vec<String>;
+    type Input = Vec<Passport>;
     type Output1 = usize;
     type Output2 = usize;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
         input
             .iter()
-            .filter(|passport| has_all_fields(passport))
+            .filter(|passport| passport.validate(&PRESENCE).is_valid())
             .count()
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
         input
             .iter()
-            .filter(|passport| has_all_fields_valid(passport))
+            .filter(|passport| passport.validate(&STRICT).is_valid())
             .count()
     }
 
@@ -44,125 +244,55 @@ impl Solver for Day04 {
             },
         );
         passport_list.push(passport.join(" "));
+
+        // Each passport is represented as a sequence of `key:value` pairs.
         passport_list
+            .iter()
+            .map(|passport| {
+                Passport(
+                    passport
+                        .split_ascii_whitespace()
+                        .map(|key_value| {
+                            let (key, value) = key_value.split_once(':').unwrap();
+                            (key.to_string(), value.to_string())
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
     }
-}
 
-fn has_all_fields(passport: &str) -> bool {
-    let mut byr_ok = false;
-    let mut iyr_ok = false;
-    let mut eyr_ok = false;
-    let mut hgt_ok = false;
-    let mut hcl_ok = false;
-    let mut ecl_ok = false;
-    let mut pid_ok = false;
-
-    // Each passport is represented as a sequence of `key:value` pairs.
-    for key_value in passport.split_ascii_whitespace() {
-        let mut key_value = key_value.split(':');
-        let key = key_value.next().unwrap();
-
-        match key {
-            "byr" => byr_ok = true,
-            "iyr" => iyr_ok = true,
-            "eyr" => eyr_ok = true,
-            "hgt" => hgt_ok = true,
-            "hcl" => hcl_ok = true,
-            "ecl" => ecl_ok = true,
-            "pid" => pid_ok = true,
-            "cid" => {} // optional
-            _ => unreachable!(),
+    fn report(&self, input: &Self::Input) {
+        if !self.explain && self.rules.is_none() {
+            return;
         }
-    }
 
-    byr_ok && iyr_ok && eyr_ok && hgt_ok && hcl_ok && ecl_ok && pid_ok
-}
+        let name = self.rules.as_deref().unwrap_or("strict");
+        let rules = rules(name).unwrap_or_else(|| panic!("unknown rule set: {}", name));
+        let reports = input
+            .iter()
+            .map(|passport| passport.validate(rules))
+            .collect::<Vec<_>>();
 
-fn has_all_fields_valid(passport: &str) -> bool {
-    let mut byr_ok = false;
-    let mut iyr_ok = false;
-    let mut eyr_ok = false;
-    let mut hgt_ok = false;
-    let mut hcl_ok = false;
-    let mut ecl_ok = false;
-    let mut pid_ok = false;
-
-    fn is_valid_year(year: &str, digit_count: usize, range: RangeInclusive<usize>) -> bool {
-        year.len() == digit_count && range.contains(&year.parse::<usize>().unwrap())
-    }
+        let valid = reports.iter().filter(|report| report.is_valid()).count();
+        println!("Valid passports ({}): {}", name, valid);
+        if !self.explain {
+            return;
+        }
 
-    // Each passport is represented as a sequence of `key:value` pairs.
-    for key_value in passport.split_ascii_whitespace() {
-        let mut key_value = key_value.split(':');
-        let key = key_value.next().unwrap();
-        let value = key_value.next().unwrap();
-
-        match key {
-            // byr (Birth Year) - four digits; at least 1920 and at most 2002.
-            "byr" => byr_ok = is_valid_year(value, 4, 1920..=2002),
-
-            // iyr (Issue Year) - four digits; at least 2010 and at most 2020.
-            "iyr" => iyr_ok = is_valid_year(value, 4, 2010..=2020),
-
-            // eyr (Expiration Year) - four digits; at least 2020 and at most 2030.
-            "eyr" => eyr_ok = is_valid_year(value, 4, 2020..=2030),
-
-            // hgt (Height) - a number followed by either cm or in:
-            //   - If cm, the number must be at least 150 and at most 193.
-            //   - If in, the number must be at least 59 and at most 76.
-            "hgt" => {
-                let (height, unit) = (&value[..value.len() - 2], &value[value.len() - 2..]);
-                hgt_ok = match unit {
-                    "cm" => (150..=193).contains(&height.parse::<usize>().unwrap()),
-                    "in" => (59..=76).contains(&height.parse::<usize>().unwrap()),
-                    _ => false,
-                }
+        for (i, report) in reports.into_iter().enumerate() {
+            if report.is_valid() {
+                println!("Passport {}: valid", i + 1);
+                continue;
             }
 
-            // hcl (Hair Color) - a # followed by exactly six characters 0-9 or a-f.
-            "hcl" => {
-                hcl_ok = value.starts_with('#')
-                    && value.len() == 7
-                    && value[1..]
-                        .chars()
-                        .all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+            println!("Passport {}: invalid", i + 1);
+            if !report.missing.is_empty() {
+                println!("  missing: {}", report.missing.join(", "));
             }
-
-            // ecl (Eye Color) - exactly one of: amb blu brn gry grn hzl oth.
-            "ecl" => {
-                ecl_ok = matches!(value, "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth")
+            for (key, value, reason) in report.invalid {
+                println!("  {}:{} ({})", key, value, reason);
             }
-
-            // pid (Passport ID) - a nine-digit number, including leading zeroes.
-            "pid" => pid_ok = value.len() == 9 && value.parse::<usize>().is_ok(),
-
-            // cid (Country ID) - ignored, missing or not.
-            "cid" => {}
-
-            _ => unreachable!(),
         }
     }
-
-    byr_ok && iyr_ok && eyr_ok && hgt_ok && hcl_ok && ecl_ok && pid_ok
-}
-
-/*
-struct Passport {
-    /// Birth Year
-    byr: Option<usize>,
-    /// Issue Year
-    iyr: Option<usize>,
-    /// Expiration Year
-    eyr: Option<usize>,
-    /// Height (in `cm` or `in`)
-    hgt: Option<usize>,
-    /// Hair Color (in hex, e.g. `#fffffd`)
-    hcl: Option<[char; 6]>,
-    /// Eye Color
-    ecl: Option<[char; 3]>,
-    /// Passport ID
-    pid: Option<usize>,
-    /// Country ID (optional, meaning it's a North Pole Credential)
-    cid: Option<Option<usize>>,
 }
-*/
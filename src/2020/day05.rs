@@ -1,58 +1,261 @@
 //! --- Day 5: Binary Boarding ---
 
 use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use std::{
+    fmt::{self, Display},
+    io::{self, BufRead, BufReader},
+};
 
 /// https://adventofcode.com/2020/day/5
-pub struct Day05;
+pub struct Day05 {
+    /// Number of rows of the plane, a power of two (defaults to 128).
+    pub rows: Option<u32>,
+    /// Number of columns of the plane, a power of two (defaults to 8).
+    pub columns: Option<u32>,
+    /// Print a map of the occupied and free seats, and the gaps between occupied seats.
+    pub map: bool,
+    /// Print the boarding pass of the seat with this ID.
+    pub encode: Option<u32>,
+    /// Print the row, column and ID of this boarding pass.
+    pub decode: Option<String>,
+}
+
+/// The seats of a plane are partitioned in halves: rows with `F` (front) or `B` (back)
+/// first, then columns with `L` (left) or `R` (right).
+#[derive(Clone, Copy, Debug)]
+struct Plane {
+    row_bits: usize,
+    column_bits: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BoardingPass {
+    row: u32,
+    column: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PassError {
+    Length {
+        expected: usize,
+        found: usize,
+    },
+    /// A character other than those partitioning rows (or columns) at `position`.
+    InvalidChar {
+        position: usize,
+        found: char,
+    },
+}
+
+impl Display for PassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PassError::Length { expected, found } => {
+                write!(f, "expected {} characters, found {}", expected, found)
+            }
+            PassError::InvalidChar { position, found } => {
+                write!(f, "invalid character at {}: {:?}", position + 1, found)
+            }
+        }
+    }
+}
+
+impl Plane {
+    fn new(rows: u32, columns: u32) -> Self {
+        assert!(rows.is_power_of_two(), "rows must be a power of two");
+        assert!(columns.is_power_of_two(), "columns must be a power of two");
+
+        Plane {
+            row_bits: rows.trailing_zeros() as usize,
+            column_bits: columns.trailing_zeros() as usize,
+        }
+    }
+
+    fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    fn columns(&self) -> u32 {
+        1 << self.column_bits
+    }
+
+    fn seat_id(&self, pass: BoardingPass) -> u32 {
+        pass.row * self.columns() + pass.column
+    }
+
+    /// Returns the seat with the given ID, if the plane has one.
+    fn seat(&self, id: u32) -> Option<BoardingPass> {
+        (id < self.rows() * self.columns()).then(|| BoardingPass {
+            row: id / self.columns(),
+            column: id % self.columns(),
+        })
+    }
+
+    fn decode(&self, pass: &str) -> Result<BoardingPass, PassError> {
+        let chars = pass.chars().collect::<Vec<_>>();
+        if chars.len() != self.row_bits + self.column_bits {
+            return Err(PassError::Length {
+                expected: self.row_bits + self.column_bits,
+                found: chars.len(),
+            });
+        }
+
+        let (row_chars, column_chars) = chars.split_at(self.row_bits);
+        let partition = |chars: &[char], offset: usize, lower: char, upper: char| {
+            chars
+                .iter()
+                .enumerate()
+                .try_fold(0, |n, (i, &found)| match found {
+                    _ if found == lower => Ok(n << 1),
+                    _ if found == upper => Ok(n << 1 | 1),
+                    _ => Err(PassError::InvalidChar {
+                        position: offset + i,
+                        found,
+                    }),
+                })
+        };
+
+        Ok(BoardingPass {
+            row: partition(row_chars, 0, 'F', 'B')?,
+            column: partition(column_chars, self.row_bits, 'L', 'R')?,
+        })
+    }
+
+    fn encode(&self, pass: BoardingPass) -> String {
+        let partition = |n: u32, bits: usize, lower: char, upper: char| {
+            (0..bits)
+                .rev()
+                .map(move |i| if n >> i & 1 == 0 { lower } else { upper })
+        };
+
+        partition(pass.row, self.row_bits, 'F', 'B')
+            .chain(partition(pass.column, self.column_bits, 'L', 'R'))
+            .collect()
+    }
+
+    /// Returns the free seats between the first and last occupied ones, as ranges
+    /// of IDs from the first to the last free seat of each gap.
+    fn gaps(&self, occupied: &[BoardingPass]) -> Vec<(u32, u32)> {
+        let mut ids = occupied
+            .iter()
+            .map(|&pass| self.seat_id(pass))
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids.windows(2)
+            .filter(|seats| seats[1] - seats[0] > 1)
+            .map(|seats| (seats[0] + 1, seats[1] - 1))
+            .collect()
+    }
+
+    /// Returns a map of the seats, a row per line with the front row first, where
+    /// occupied seats are `#`, free seats between occupied ones `O`, and others `.`.
+    fn render(&self, occupied: &[BoardingPass]) -> String {
+        let gaps = self.gaps(occupied);
+        let mut map = String::new();
+
+        for row in 0..self.rows() {
+            map += &format!("{:>4} ", row);
+            for column in 0..self.columns() {
+                let pass = BoardingPass { row, column };
+                let id = self.seat_id(pass);
+                map.push(if occupied.contains(&pass) {
+                    '#'
+                } else if gaps
+                    .iter()
+                    .any(|&(first, last)| (first..=last).contains(&id))
+                {
+                    'O'
+                } else {
+                    '.'
+                });
+            }
+            map.push('\n');
+        }
+
+        map
+    }
+}
+
+impl Day05 {
+    fn plane(&self) -> Plane {
+        Plane::new(self.rows.unwrap_or(128), self.columns.unwrap_or(8))
+    }
+}
 
 impl Solver for Day05 {
-    type Input = Vec<u16>;
-    type Output1 = u16;
-    type Output2 = u16;
+    type Input = Vec<BoardingPass>;
+    type Output1 = u32;
+    type Output2 = u32;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
         // What is the highest seat ID on a boarding pass?
-        *input.iter().max().unwrap()
+        let plane = self.plane();
+
+        input.iter().map(|&pass| plane.seat_id(pass)).max().unwrap()
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let input = {
-            let mut input = input.clone();
-            input.sort_unstable();
-            input
-        };
-
         // What is the ID of your seat?
-        for seats in input.windows(2) {
-            if let [left, right] = seats {
-                // Your seat wasn't at the very front or back, though;
-                // the seats with IDs +1 and -1 from yours will be in your list.
-                if right - left == 2 {
-                    return left + 1;
-                }
-            }
-        }
+        // Your seat wasn't at the very front or back, though;
+        // the seats with IDs +1 and -1 from yours will be in your list.
+        let (first, _) = self
+            .plane()
+            .gaps(input)
+            .into_iter()
+            .find(|&(first, last)| first == last)
+            .unwrap();
 
-        unreachable!();
+        first
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
+        let plane = self.plane();
+
         BufReader::new(r)
             .lines()
             .flatten()
-            .map(|line| {
-                line.bytes().fold(0, |seat_id, seat_char| {
-                    (seat_id << 1)
-                        + match seat_char as char {
-                            'F' => 0, // front
-                            'B' => 1, // back
-                            'L' => 0, // left
-                            'R' => 1, // right
-                            _ => unreachable!(),
-                        } as u16
+            .enumerate()
+            .map(|(i, line)| {
+                plane.decode(&line).unwrap_or_else(|err| {
+                    panic!("invalid boarding pass on line {}: {}", i + 1, err)
                 })
             })
             .collect::<Vec<_>>()
     }
+
+    fn report(&self, input: &Self::Input) {
+        let plane = self.plane();
+
+        if let Some(pass) = &self.decode {
+            match plane.decode(pass) {
+                Ok(seat) => println!(
+                    "{}: row {}, column {}, seat ID {}",
+                    pass,
+                    seat.row,
+                    seat.column,
+                    plane.seat_id(seat)
+                ),
+                Err(err) => println!("{}: {}", pass, err),
+            }
+        }
+
+        if let Some(id) = self.encode {
+            match plane.seat(id) {
+                Some(seat) => println!("Seat ID {}: {}", id, plane.encode(seat)),
+                None => println!("Seat ID {}: no such seat on the plane", id),
+            }
+        }
+
+        if self.map {
+            print!("{}", plane.render(input));
+            for (first, last) in plane.gaps(input) {
+                match first == last {
+                    true => println!("Free seat: {}", first),
+                    false => println!("Free seats: {} to {}", first, last),
+                }
+            }
+        }
+    }
 }
@@ -1,15 +1,163 @@
 //! --- Day 6: Custom Customs ---
 
 use crate::solver::Solver;
-use std::io;
+use std::{io, str::FromStr};
 
 /// https://adventofcode.com/2020/day/6
-pub struct Day06;
+pub struct Day06 {
+    /// Questions that can be answered, one character each (defaults to 'a' to 'z').
+    pub alphabet: Option<String>,
+    /// Print the sum over groups of the number of questions in this aggregation of
+    /// each group's answers.
+    pub aggregation: Option<Aggregation>,
+}
+
+const QUESTIONS: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// Questions answered "yes" by a person (or a group), as a set of their indices
+/// in the alphabet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Answer(Vec<u64>);
+
+/// How the answers of every person in a group are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Questions to which anyone answered "yes".
+    Union,
+    /// Questions to which everyone answered "yes".
+    Intersection,
+    /// Questions to which an odd number of people answered "yes".
+    SymmetricDifference,
+    /// Questions to which at least this many people answered "yes".
+    AtLeast(usize),
+}
+
+impl FromStr for Aggregation {
+    type Err = String;
+
+    /// Parses "union", "intersection", "symmetric-difference" or "at-least:K".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "union" => Ok(Aggregation::Union),
+            "intersection" => Ok(Aggregation::Intersection),
+            "symmetric-difference" => Ok(Aggregation::SymmetricDifference),
+            _ => s
+                .strip_prefix("at-least:")
+                .and_then(|k| k.parse().ok())
+                .map(Aggregation::AtLeast)
+                .ok_or(format!("unknown aggregation: {}", s)),
+        }
+    }
+}
+
+impl Answer {
+    fn empty(alphabet: &[char]) -> Self {
+        Answer(vec![0; alphabet.len().div_ceil(64)])
+    }
+
+    fn full(alphabet: &[char]) -> Self {
+        let mut answer = Answer::empty(alphabet);
+        for i in 0..alphabet.len() {
+            answer.0[i / 64] |= 1 << (i % 64);
+        }
+        answer
+    }
+
+    fn new(person_answer: &str, alphabet: &[char]) -> Self {
+        let mut answer = Answer::empty(alphabet);
+        for question in person_answer.chars() {
+            let i = alphabet
+                .iter()
+                .position(|&c| c == question)
+                .unwrap_or_else(|| panic!("question {:?} isn't in the alphabet", question));
+            answer.0[i / 64] |= 1 << (i % 64);
+        }
+        answer
+    }
+
+    fn yes_count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    fn combine(&self, other: &Answer, f: impl Fn(u64, u64) -> u64) -> Answer {
+        Answer(
+            self.0
+                .iter()
+                .zip(&other.0)
+                .map(|(&a, &b)| f(a, b))
+                .collect(),
+        )
+    }
+
+    fn union(&self, other: &Answer) -> Answer {
+        self.combine(other, |a, b| a | b)
+    }
+
+    fn intersection(&self, other: &Answer) -> Answer {
+        self.combine(other, |a, b| a & b)
+    }
+
+    fn symmetric_difference(&self, other: &Answer) -> Answer {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    /// Returns the questions answered "yes" in at least `k` of the `answers`.
+    ///
+    /// The questions answered at least `j` times are tracked for every `j` up to `k`,
+    /// and each answer promotes those answered `j - 1` times to `j`.
+    fn at_least(answers: &[Answer], k: usize, alphabet: &[char]) -> Answer {
+        let mut at_least = vec![Answer::empty(alphabet); k + 1];
+        at_least[0] = Answer::full(alphabet);
+
+        for answer in answers {
+            for j in (1..=k).rev() {
+                at_least[j] = at_least[j].union(&at_least[j - 1].intersection(answer));
+            }
+        }
 
-const QUESTION_COUNT: usize = 26; // 'a'..='z'
+        at_least.swap_remove(k)
+    }
 
-#[derive(Copy, Clone)]
-pub struct Answer([bool; QUESTION_COUNT]);
+    fn aggregate(answers: &[Answer], aggregation: Aggregation, alphabet: &[char]) -> Answer {
+        match aggregation {
+            Aggregation::Union => answers
+                .iter()
+                .fold(Answer::empty(alphabet), |acc, answer| acc.union(answer)),
+            Aggregation::Intersection => {
+                answers.iter().fold(Answer::full(alphabet), |acc, answer| {
+                    acc.intersection(answer)
+                })
+            }
+            Aggregation::SymmetricDifference => {
+                answers.iter().fold(Answer::empty(alphabet), |acc, answer| {
+                    acc.symmetric_difference(answer)
+                })
+            }
+            Aggregation::AtLeast(k) => Answer::at_least(answers, k, alphabet),
+        }
+    }
+}
+
+impl Day06 {
+    fn alphabet(&self) -> Vec<char> {
+        self.alphabet
+            .as_deref()
+            .unwrap_or(QUESTIONS)
+            .chars()
+            .collect()
+    }
+
+    fn sum_of_counts(&self, input: &[Vec<Answer>], aggregation: Aggregation) -> usize {
+        let alphabet = self.alphabet();
+
+        input
+            .iter()
+            .map(|group_answers| {
+                Answer::aggregate(group_answers, aggregation, &alphabet).yes_count()
+            })
+            .sum()
+    }
+}
 
 impl Solver for Day06 {
     type Input = Vec<Vec<Answer>>;
@@ -17,73 +165,41 @@ impl Solver for Day06 {
     type Output2 = usize;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        input
-            .iter()
-            .map(|group_answers| Answer::union_of(group_answers).unwrap().yes_count())
-            .sum()
+        self.sum_of_counts(input, Aggregation::Union)
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        input
-            .iter()
-            .map(|group_answers| Answer::intersection_of(group_answers).unwrap().yes_count())
-            .sum()
+        self.sum_of_counts(input, Aggregation::Intersection)
     }
 
     fn parse_input<R: io::Read>(&self, mut r: R) -> Self::Input {
         let mut input = String::new();
         r.read_to_string(&mut input).unwrap();
+        let alphabet = self.alphabet();
 
         // Each group's answers are separated by a blank line, and
         // within each group, each person's answers are on a single line.
+        // Extra blank lines (e.g. at the end) don't make an empty group, whose
+        // intersection would be every question.
         input
             .split("\n\n")
             .map(|group_answers| {
                 group_answers
                     .split_whitespace()
-                    .map(Answer::new)
+                    .map(|person_answer| Answer::new(person_answer, &alphabet))
                     .collect::<Vec<_>>()
             })
+            .filter(|group_answers| !group_answers.is_empty())
             .collect::<Vec<_>>()
     }
-}
-
-impl Answer {
-    fn new(person_answer: &str) -> Self {
-        let mut answer = [false; QUESTION_COUNT];
-        for (i, question) in ('a'..='z').enumerate() {
-            answer[i] = person_answer.contains(question);
-        }
-        Answer(answer)
-    }
-
-    fn yes_count(&self) -> usize {
-        self.0.iter().filter(|&&answer| answer).count()
-    }
-
-    fn union_of(answers: &[Answer]) -> Option<Answer> {
-        Self::collapse(answers, |a, b| a || b)
-    }
-
-    fn intersection_of(answers: &[Answer]) -> Option<Answer> {
-        Self::collapse(answers, |a, b| a && b)
-    }
 
-    fn collapse<F>(answers: &[Answer], f: F) -> Option<Answer>
-    where
-        F: Fn(bool, bool) -> bool,
-    {
-        match answers {
-            [first_answer, answers @ ..] => Some(answers.iter().fold(
-                *first_answer,
-                |mut collapsed_answer, answer| {
-                    for i in 0..QUESTION_COUNT {
-                        collapsed_answer.0[i] = f(collapsed_answer.0[i], answer.0[i]);
-                    }
-                    collapsed_answer
-                },
-            )),
-            _ => None,
+    fn report(&self, input: &Self::Input) {
+        if let Some(aggregation) = self.aggregation {
+            println!(
+                "Sum of counts ({:?}): {}",
+                aggregation,
+                self.sum_of_counts(input, aggregation)
+            );
         }
     }
 }
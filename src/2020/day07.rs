@@ -3,11 +3,18 @@
 use crate::solver::Solver;
 use std::{
     collections::{HashMap, HashSet},
+    fs,
     io::{self, BufRead, BufReader},
+    iter::once,
 };
 
 /// https://adventofcode.com/2020/day/7
-pub struct Day07;
+pub struct Day07 {
+    /// Print the ancestors, descendants, total contents and deepest chain of this bag.
+    pub bag: Option<String>,
+    /// Write the containment graph to this file, in Graphviz DOT format.
+    pub dot: Option<String>,
+}
 
 #[derive(Default)]
 /// Map of each bag to its rules.
@@ -30,39 +37,50 @@ impl Solver for Day07 {
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
         // How many bag colors can eventually contain at least one shiny gold bag?
-        if let Some(my_containers) = input.0.get(MY_BAG).map(|my_bag| &my_bag.containers) {
-            let mut my_containers = my_containers.clone();
-            loop {
-                let mut more_containers = HashSet::new();
-
-                for container in &my_containers {
-                    if let Some(its_containers) = input
-                        .0
-                        .get(container)
-                        .map(|my_container| &my_container.containers)
-                    {
-                        for its_container in its_containers {
-                            if !my_containers.contains(its_container) {
-                                more_containers.insert(its_container.clone());
-                            }
-                        }
-                    }
-                }
-
-                if more_containers.is_empty() {
-                    break my_containers.len();
-                }
-
-                my_containers.extend(more_containers);
-            }
-        } else {
-            0
-        }
+        input.ancestors(MY_BAG).len()
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
         // How many individual bags are required inside your single shiny gold bag?
-        input.max_amount_inside(MY_BAG)
+        input
+            .total_contents(MY_BAG)
+            .unwrap_or_else(|cycle| panic!("bags form a cycle: {}", cycle.join(" -> ")))
+    }
+
+    fn report(&self, input: &Self::Input) {
+        if self.bag.is_none() && self.dot.is_none() {
+            return;
+        }
+
+        if let Some(cycle) = input.find_cycle(input.0.keys().map(String::as_str)) {
+            println!("Bags form a cycle: {}", cycle.join(" -> "));
+        }
+
+        if let Some(bag) = &self.bag {
+            let sorted = |bags: HashSet<&String>| {
+                let mut bags = bags.into_iter().map(String::as_str).collect::<Vec<_>>();
+                bags.sort_unstable();
+                format!("({}) {}", bags.len(), bags.join(", "))
+            };
+            let or_cycle = |result: Result<String, Vec<String>>| {
+                result.unwrap_or_else(|cycle| format!("infinite ({})", cycle.join(" -> ")))
+            };
+
+            println!("Ancestors: {}", sorted(input.ancestors(bag)));
+            println!("Descendants: {}", sorted(input.descendants(bag)));
+            println!(
+                "Total contents: {}",
+                or_cycle(input.total_contents(bag).map(|total| total.to_string()))
+            );
+            println!(
+                "Deepest chain: {}",
+                or_cycle(input.deepest_chain(bag).map(|chain| chain.join(" > ")))
+            );
+        }
+
+        if let Some(path) = &self.dot {
+            fs::write(path, input.to_dot()).expect("unable to write DOT file");
+        }
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
@@ -111,16 +129,159 @@ impl Bags {
             .insert(container_bag);
     }
 
-    fn max_amount_inside(&self, bag: &str) -> usize {
-        match self.0.get(bag) {
-            Some(bag) => bag
-                .contents
-                .iter()
-                .fold(0, |acc, (contained_bag, max_amount)| {
-                    acc + max_amount * (1 + self.max_amount_inside(contained_bag))
-                }),
-            None => 0,
+    fn contents_of<'a>(&'a self, bag: &str) -> impl Iterator<Item = (&'a String, usize)> {
+        self.0
+            .get(bag)
+            .into_iter()
+            .flat_map(|rule| rule.contents.iter().map(|(bag, &amount)| (bag, amount)))
+    }
+
+    /// Returns all bags that can eventually contain `bag`.
+    fn ancestors(&self, bag: &str) -> HashSet<&String> {
+        let mut ancestors = HashSet::new();
+        let mut stack = vec![bag];
+
+        while let Some(bag) = stack.pop() {
+            for container in self
+                .0
+                .get(bag)
+                .into_iter()
+                .flat_map(|rule| &rule.containers)
+            {
+                if ancestors.insert(container) {
+                    stack.push(container);
+                }
+            }
         }
+
+        ancestors
+    }
+
+    /// Returns all bags that `bag` can eventually contain.
+    fn descendants(&self, bag: &str) -> HashSet<&String> {
+        let mut descendants = HashSet::new();
+        let mut stack = vec![bag];
+
+        while let Some(bag) = stack.pop() {
+            for (contained_bag, _) in self.contents_of(bag) {
+                if descendants.insert(contained_bag) {
+                    stack.push(contained_bag);
+                }
+            }
+        }
+
+        descendants
+    }
+
+    /// Returns the bags in a cycle (starting and ending with the same bag) that can be
+    /// reached from `bags`, if there is any, as then a bag would (eventually) contain itself.
+    fn find_cycle<'a>(&'a self, bags: impl Iterator<Item = &'a str>) -> Option<Vec<String>> {
+        fn visit<'a>(
+            bags: &'a Bags,
+            bag: &'a str,
+            is_done: &mut HashMap<&'a str, bool>,
+            path: &mut Vec<&'a str>,
+        ) -> Option<Vec<String>> {
+            match is_done.get(bag) {
+                Some(true) => return None,
+                Some(false) => {
+                    let start = path.iter().position(|&b| b == bag).unwrap();
+                    let cycle = path[start..].iter().chain(once(&bag));
+                    return Some(cycle.map(|bag| bag.to_string()).collect());
+                }
+                None => {}
+            }
+
+            is_done.insert(bag, false);
+            path.push(bag);
+            for (contained_bag, _) in bags.contents_of(bag) {
+                if let Some(cycle) = visit(bags, contained_bag, is_done, path) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            is_done.insert(bag, true);
+
+            None
+        }
+
+        let mut is_done = HashMap::new();
+        let mut bags = bags.collect::<Vec<_>>();
+        bags.sort_unstable();
+
+        bags.into_iter()
+            .find_map(|bag| visit(self, bag, &mut is_done, &mut vec![]))
+    }
+
+    /// Returns how many individual bags are required inside `bag`,
+    /// or a cycle if it would contain itself (and so, infinitely many).
+    fn total_contents(&self, bag: &str) -> Result<usize, Vec<String>> {
+        fn count<'a>(bags: &'a Bags, bag: &'a str, memo: &mut HashMap<&'a str, usize>) -> usize {
+            if let Some(&total) = memo.get(bag) {
+                return total;
+            }
+
+            let total = bags
+                .contents_of(bag)
+                .map(|(contained_bag, amount)| amount * (1 + count(bags, contained_bag, memo)))
+                .sum();
+            memo.insert(bag, total);
+
+            total
+        }
+
+        match self.find_cycle(once(bag)) {
+            Some(cycle) => Err(cycle),
+            None => Ok(count(self, bag, &mut HashMap::new())),
+        }
+    }
+
+    /// Returns the longest chain of bags, each directly inside the previous one, starting
+    /// with `bag`, or a cycle if it would contain itself (and so, be infinitely long).
+    fn deepest_chain(&self, bag: &str) -> Result<Vec<String>, Vec<String>> {
+        fn chain<'a>(
+            bags: &'a Bags,
+            bag: &'a str,
+            memo: &mut HashMap<&'a str, Vec<&'a str>>,
+        ) -> Vec<&'a str> {
+            if let Some(chain) = memo.get(bag) {
+                return chain.clone();
+            }
+
+            let mut deepest = bags
+                .contents_of(bag)
+                .map(|(contained_bag, _)| chain(bags, contained_bag, memo))
+                .max_by_key(|chain| chain.len())
+                .unwrap_or_default();
+            deepest.insert(0, bag);
+            memo.insert(bag, deepest.clone());
+
+            deepest
+        }
+
+        match self.find_cycle(once(bag)) {
+            Some(cycle) => Err(cycle),
+            None => Ok(chain(self, bag, &mut HashMap::new())
+                .into_iter()
+                .map(String::from)
+                .collect()),
+        }
+    }
+
+    /// Returns the containment graph in Graphviz DOT format.
+    fn to_dot(&self) -> String {
+        let mut edges = self
+            .0
+            .keys()
+            .flat_map(|bag| {
+                self.contents_of(bag).map(move |(contained_bag, amount)| {
+                    format!("    {:?} -> {:?} [label={}];", bag, contained_bag, amount)
+                })
+            })
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+
+        format!("digraph bags {{\n{}\n}}\n", edges.join("\n"))
     }
 }
 
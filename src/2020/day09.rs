@@ -1,92 +1,163 @@
 //! --- Day 9: Encoding Error ---
 
 use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader},
+};
 
 /// https://adventofcode.com/2020/day/9
-pub struct Day09;
+pub struct Day09 {
+    /// Number of previous numbers that the next one must be the sum of two of (defaults to 25).
+    pub preamble: Option<usize>,
+    /// Print every invalid number (and the weakness range it's the sum of, if any).
+    pub all_invalid: bool,
+}
 
 const PREAMBLE_LENGTH: usize = 25;
 
-fn sum_two(numbers: &[usize], target: usize) -> Option<(usize, usize)> {
-    for i in 1..numbers.len() {
-        for j in 0..i {
-            if target == numbers[i] + numbers[j] {
-                return Some((numbers[i], numbers[j]));
+/// Iterator over the invalid numbers of a stream, i.e. those (after the preamble) which
+/// aren't the sum of two different numbers among the `preamble` ones before them.
+struct Validator<I> {
+    numbers: I,
+    preamble: usize,
+    /// Index of the next number.
+    index: usize,
+    window: VecDeque<u64>,
+    /// Number of pairs of (different) numbers in the window adding up to each sum.
+    pair_sums: HashMap<u64, usize>,
+}
+
+impl<I: Iterator<Item = u64>> Validator<I> {
+    fn new(numbers: I, preamble: usize) -> Self {
+        assert!(preamble > 0, "the preamble must have at least one number");
+
+        Validator {
+            numbers,
+            preamble,
+            index: 0,
+            window: VecDeque::with_capacity(preamble + 1),
+            pair_sums: HashMap::new(),
+        }
+    }
+
+    fn is_valid(&self, number: u64) -> bool {
+        self.window.len() < self.preamble || self.pair_sums.get(&number).is_some_and(|&n| n > 0)
+    }
+
+    /// Slides the window to include `number`, updating the sums of pairs in it.
+    fn slide(&mut self, number: u64) {
+        if self.window.len() == self.preamble {
+            let oldest = self.window.pop_front().unwrap();
+            for &other in self.window.iter().filter(|&&other| other != oldest) {
+                *self.pair_sums.get_mut(&(oldest + other)).unwrap() -= 1;
             }
         }
+
+        for &other in self.window.iter().filter(|&&other| other != number) {
+            *self.pair_sums.entry(number + other).or_insert(0) += 1;
+        }
+        self.window.push_back(number);
     }
-    None
 }
 
-fn min_max(range: &[usize]) -> Option<(usize, usize)> {
-    if range.is_empty() {
-        return None;
+impl<I: Iterator<Item = u64>> Iterator for Validator<I> {
+    /// Index and value of an invalid number.
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(number) = self.numbers.next() {
+            let index = self.index;
+            self.index += 1;
+
+            let is_valid = self.is_valid(number);
+            self.slide(number);
+            if !is_valid {
+                return Some((index, number));
+            }
+        }
+
+        None
     }
+}
 
-    let mut range = range.iter();
-    let first = *range.next().unwrap();
+/// Returns the first and last indices of the first contiguous range of at least two
+/// `numbers` which add up to `target`, moving either end of the range towards the end.
+fn weakness_range(numbers: &[u64], target: u64) -> Option<(usize, usize)> {
+    let (mut first, mut sum) = (0, 0);
 
-    Some(range.fold((first, first), |(smallest, largest), &number| {
-        if number < smallest {
-            (number, largest)
-        } else if number > largest {
-            (smallest, number)
-        } else {
-            (smallest, largest)
+    for (last, &number) in numbers.iter().enumerate() {
+        sum += number;
+        while sum > target && first < last {
+            sum -= numbers[first];
+            first += 1;
         }
-    }))
+        if sum == target && first < last {
+            return Some((first, last));
+        }
+    }
+
+    None
+}
+
+impl Day09 {
+    fn invalid_numbers<'a>(&self, input: &'a [u64]) -> Validator<impl Iterator<Item = u64> + 'a> {
+        Validator::new(
+            input.iter().copied(),
+            self.preamble.unwrap_or(PREAMBLE_LENGTH),
+        )
+    }
 }
 
 impl Solver for Day09 {
-    type Input = Vec<usize>;
-    type Output1 = usize;
-    type Output2 = usize;
+    type Input = Vec<u64>;
+    type Output1 = u64;
+    type Output2 = u64;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
         // Find the first number in the list (after the preamble) which
         // is not the sum of two of the 25 numbers before it.
-        for i in PREAMBLE_LENGTH..input.len() {
-            let preamble = &input[i - PREAMBLE_LENGTH..i];
-            let number = input[i];
-            if sum_two(preamble, number).is_none() {
-                return number;
-            }
-        }
+        let (_, invalid_number) = self.invalid_numbers(input).next().unwrap();
 
-        unreachable!()
+        invalid_number
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
         // Find a contiguous set of at least two numbers in your list
         // which sum to the invalid number from part 1.
         let invalid_number = self.solve_part1(input);
-
-        let (mut first, mut last, mut sum) = (0, 0, input[0]);
-
-        while sum != invalid_number {
-            while sum < invalid_number {
-                last += 1;
-                sum += input[last];
-            }
-            while sum > invalid_number {
-                sum -= input[first];
-                first += 1;
-            }
-        }
+        let (first, last) = weakness_range(input, invalid_number).unwrap();
 
         // To find the encryption weakness, add together the smallest
         // and largest number in this contiguous range.
-        let (smallest, largest) = min_max(&input[first..=last]).unwrap();
-
-        smallest + largest
+        let range = &input[first..=last];
+        range.iter().min().unwrap() + range.iter().max().unwrap()
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
         BufReader::new(r)
             .lines()
             .flatten()
-            .flat_map(|line| line.parse::<usize>())
+            .flat_map(|line| line.parse::<u64>())
             .collect::<Vec<_>>()
     }
+
+    fn report(&self, input: &Self::Input) {
+        if !self.all_invalid {
+            return;
+        }
+
+        for (index, number) in self.invalid_numbers(input) {
+            match weakness_range(input, number) {
+                Some((first, last)) => println!(
+                    "{} (at index {}) is the sum of the numbers from index {} to {}",
+                    number, index, first, last
+                ),
+                None => println!(
+                    "{} (at index {}) isn't the sum of a contiguous range",
+                    number, index
+                ),
+            }
+        }
+    }
 }
@@ -1,6 +1,6 @@
 //! --- Day 10: Adapter Array ---
 
-use crate::solver::Solver;
+use crate::{bigint::BigUint, solver::Solver};
 use std::io::{self, BufRead, BufReader};
 
 /// https://adventofcode.com/2020/day/10
@@ -17,7 +17,7 @@ const MAX_DELTA: usize = 3;
 impl Solver for Day10 {
     type Input = Vec<usize>;
     type Output1 = usize;
-    type Output2 = usize;
+    type Output2 = BigUint;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
         // What is the number of 1-jolt differences multiplied by
@@ -40,25 +40,19 @@ impl Solver for Day10 {
         // adapters to connect the charging outlet to your device?
         let mut adapter_arrangements = input
             .iter()
-            .map(|&adapter| (adapter - CHARGING_OUTLET <= MAX_DELTA) as usize)
+            .map(|&adapter| BigUint::from((adapter - CHARGING_OUTLET <= MAX_DELTA) as u64))
             .collect::<Vec<_>>();
 
         for i in 0..input.len() {
-            adapter_arrangements[i] += (0..i)
+            let arrangements = (0..i)
                 .rev()
-                .map(|j| {
-                    if input[i] - input[j] <= MAX_DELTA {
-                        Some(adapter_arrangements[j])
-                    } else {
-                        None
-                    }
-                })
-                .take_while(|arrangements| arrangements.is_some())
-                .flatten()
-                .sum::<usize>();
+                .take_while(|&j| input[i] - input[j] <= MAX_DELTA)
+                .map(|j| &adapter_arrangements[j])
+                .sum::<BigUint>();
+            adapter_arrangements[i] += &arrangements;
         }
 
-        *adapter_arrangements.last().unwrap()
+        adapter_arrangements.pop().unwrap()
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
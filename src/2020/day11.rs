@@ -1,10 +1,20 @@
 //! --- Day 11: Seating System ---
 
 use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader},
+};
 
 /// https://adventofcode.com/2020/day/11
-pub struct Day11;
+pub struct Day11 {
+    /// An empty seat becomes occupied when at most this many of its neighbors are.
+    pub occupy: Option<usize>,
+    /// An occupied seat becomes empty when at least this many of its neighbors are.
+    pub vacate: Option<usize>,
+    /// Neighbors are the first seats in sight (instead of the adjacent ones).
+    pub in_sight: bool,
+}
 
 #[derive(Clone, Debug)]
 pub struct SeatingArea {
@@ -27,88 +37,161 @@ const NEIGHBORHOOD: [(i32, i32); 8] = [
     (-1,  1), (0,  1), (1,  1),
 ];
 
-// @Cleanup: add borders to the seating area and remove bound checks.
-
-fn adjacent(s: &SeatingArea, row: usize, col: usize) -> Vec<Seat> {
-    NEIGHBORHOOD
-        .iter()
-        .map(|&(dx, dy)| {
-            let y = row as i32 + dy;
-            let x = col as i32 + dx;
-            if (0..s.rows as i32).contains(&y) && (0..s.cols as i32).contains(&x) {
-                Some(s.seats[y as usize][x as usize])
-            } else {
-                None
-            }
-        })
-        .flatten()
-        .collect()
+/// Which seats are the neighbors of a seat.
+#[derive(Clone, Copy)]
+enum Visibility {
+    /// The seats adjacent to it.
+    Adjacent,
+    /// The first seat in sight in each direction.
+    InSight,
 }
 
-fn in_sight(s: &SeatingArea, row: usize, col: usize) -> Vec<Seat> {
-    NEIGHBORHOOD
-        .iter()
-        .map(|&(dx, dy)| {
-            let mut y = row as i32 + dy;
-            let mut x = col as i32 + dx;
-
-            while (0..s.rows as i32).contains(&y)
-                && (0..s.cols as i32).contains(&x)
-                && s.seats[y as usize][x as usize] == Seat::Floor
-            {
-                y += dy;
-                x += dx;
-            }
+/// An empty seat becomes occupied when at most `occupy` of its neighbors are,
+/// and an occupied seat becomes empty when at least `vacate` of them are.
+#[derive(Clone, Copy)]
+struct Rules {
+    visibility: Visibility,
+    occupy: usize,
+    vacate: usize,
+}
 
-            if (0..s.rows as i32).contains(&y) && (0..s.cols as i32).contains(&x) {
-                Some(s.seats[y as usize][x as usize])
-            } else {
-                None
-            }
-        })
-        .flatten()
-        .collect()
+/// How the seating area ends up, after applying the rules `step` times.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    /// No seats change state anymore.
+    Equilibrium { step: usize },
+    /// Seats go through the same states every `period` steps.
+    Cycle { step: usize, period: usize },
 }
 
-fn neighbors<'s, S>(seats: S) -> usize
-where
-    S: IntoIterator<Item = &'s Seat>,
-{
-    seats
-        .into_iter()
-        .filter(|&&neighbor| neighbor == Seat::Occupied)
-        .count()
+/// Seats (without the floor, which never changes) and their neighbors, by index.
+struct Simulator {
+    neighbors: Vec<Vec<usize>>,
+    occupied: Vec<bool>,
+    rules: Rules,
 }
 
 impl SeatingArea {
-    fn next<N>(
-        &mut self,
-        neighborhood: N,
-        become_occupied: fn(usize) -> bool,
-        become_empty: fn(usize) -> bool,
-    ) -> bool
-    where
-        N: Fn(&Self, usize, usize) -> Vec<Seat>,
-    {
-        let mut next_seats = self.seats.clone();
-        let mut changed = false;
-
-        (0..self.rows).for_each(|row| {
-            (0..self.cols).for_each(|col| match self.seats[row][col] {
-                Seat::Empty if become_occupied(neighbors(&neighborhood(self, row, col))) => {
-                    next_seats[row][col] = Seat::Occupied;
-                    changed = true;
+    /// Returns the seat (as its index among seats) that is the neighbor of the one
+    /// at `(row, col)` in the direction `(dx, dy)`, if any.
+    fn neighbor(
+        &self,
+        ids: &[Vec<Option<usize>>],
+        (row, col): (usize, usize),
+        (dx, dy): (i32, i32),
+        visibility: Visibility,
+    ) -> Option<usize> {
+        let (mut y, mut x) = (row as i32 + dy, col as i32 + dx);
+
+        while (0..self.rows as i32).contains(&y) && (0..self.cols as i32).contains(&x) {
+            match (ids[y as usize][x as usize], visibility) {
+                (Some(id), _) => return Some(id),
+                (None, Visibility::Adjacent) => return None,
+                (None, Visibility::InSight) => {
+                    y += dy;
+                    x += dx;
                 }
-                Seat::Occupied if become_empty(neighbors(&neighborhood(self, row, col))) => {
-                    next_seats[row][col] = Seat::Empty;
-                    changed = true;
+            }
+        }
+
+        None
+    }
+
+    fn simulator(&self, rules: Rules) -> Simulator {
+        let mut ids = vec![vec![None; self.cols]; self.rows];
+        let mut seats = vec![];
+        for (row, line) in self.seats.iter().enumerate() {
+            for (col, &seat) in line.iter().enumerate() {
+                if seat != Seat::Floor {
+                    ids[row][col] = Some(seats.len());
+                    seats.push((row, col));
+                }
+            }
+        }
+
+        Simulator {
+            neighbors: seats
+                .iter()
+                .map(|&seat| {
+                    NEIGHBORHOOD
+                        .iter()
+                        .filter_map(|&direction| {
+                            self.neighbor(&ids, seat, direction, rules.visibility)
+                        })
+                        .collect()
+                })
+                .collect(),
+            occupied: seats
+                .iter()
+                .map(|&(row, col)| self.seats[row][col] == Seat::Occupied)
+                .collect(),
+            rules,
+        }
+    }
+}
+
+impl Simulator {
+    /// Applies the rules to every seat simultaneously.
+    fn next(&mut self) {
+        self.occupied = self
+            .neighbors
+            .iter()
+            .zip(&self.occupied)
+            .map(|(neighbors, &occupied)| {
+                let occupied_neighbors = neighbors.iter().filter(|&&n| self.occupied[n]).count();
+                if occupied {
+                    occupied_neighbors < self.rules.vacate
+                } else {
+                    occupied_neighbors <= self.rules.occupy
                 }
-                _ => {}
             })
-        });
+            .collect();
+    }
+
+    /// Returns which seats are occupied, packed into words.
+    fn state(&self) -> Vec<u64> {
+        self.occupied
+            .chunks(64)
+            .map(|seats| {
+                seats
+                    .iter()
+                    .rev()
+                    .fold(0, |word, &occupied| word << 1 | occupied as u64)
+            })
+            .collect()
+    }
+
+    /// Applies the rules until the seats are in a state they've already been in.
+    fn run(&mut self) -> Outcome {
+        let mut seen = HashMap::new();
+
+        for step in 0.. {
+            if let Some(first_step) = seen.insert(self.state(), step) {
+                return match step - first_step {
+                    1 => Outcome::Equilibrium { step: first_step },
+                    period => Outcome::Cycle {
+                        step: first_step,
+                        period,
+                    },
+                };
+            }
+            self.next();
+        }
+
+        unreachable!()
+    }
 
-        self.seats = next_seats;
-        changed
+    fn occupied_seats(&self) -> usize {
+        self.occupied.iter().filter(|&&occupied| occupied).count()
+    }
+}
+
+/// Returns the number of occupied seats once equilibrium is reached.
+fn occupied_at_equilibrium(seating_area: &SeatingArea, rules: Rules) -> usize {
+    let mut simulator = seating_area.simulator(rules);
+    match simulator.run() {
+        Outcome::Equilibrium { .. } => simulator.occupied_seats(),
+        outcome => panic!("no equilibrium: {:?}", outcome),
     }
 }
 
@@ -117,42 +200,28 @@ impl Solver for Day11 {
     type Output1 = usize;
     type Output2 = usize;
 
+    /// Simulate your seating area by applying the seating rules repeatedly
+    /// until no seats change state. How many seats end up occupied?
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let mut seating_area = input.clone();
-
-        // Seating rules:
-        fn become_occupied(neighbors: usize) -> bool {
-            neighbors == 0
-        }
-        fn become_empty(neighbors: usize) -> bool {
-            neighbors >= 4
-        }
-        let neighborhood = adjacent;
+        let rules = Rules {
+            visibility: Visibility::Adjacent,
+            occupy: 0,
+            vacate: 4,
+        };
 
-        // Simulate your seating area by applying the seating rules repeatedly
-        // until no seats change state. How many seats end up occupied?
-        while seating_area.next(neighborhood, become_occupied, become_empty) {}
-
-        neighbors(seating_area.seats.iter().flatten())
+        occupied_at_equilibrium(input, rules)
     }
 
+    /// Given the new visibility method and the rule change for occupied seats
+    /// becoming empty, once equilibrium is reached, how many seats end up occupied?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let mut seating_area = input.clone();
-
-        // New seating rules:
-        fn become_occupied(neighbors: usize) -> bool {
-            neighbors == 0
-        }
-        fn become_empty(neighbors: usize) -> bool {
-            neighbors >= 5
-        }
-        let neighborhood = in_sight;
-
-        // Given the new visibility method and the rule change for occupied seats
-        // becoming empty, once equilibrium is reached, how many seats end up occupied?
-        while seating_area.next(neighborhood, become_occupied, become_empty) {}
+        let rules = Rules {
+            visibility: Visibility::InSight,
+            occupy: 0,
+            vacate: 5,
+        };
 
-        neighbors(seating_area.seats.iter().flatten())
+        occupied_at_equilibrium(input, rules)
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
@@ -176,4 +245,36 @@ impl Solver for Day11 {
 
         SeatingArea { seats, rows, cols }
     }
+
+    fn report(&self, input: &Self::Input) {
+        if self.occupy.is_none() && self.vacate.is_none() && !self.in_sight {
+            return;
+        }
+
+        let rules = Rules {
+            visibility: if self.in_sight {
+                Visibility::InSight
+            } else {
+                Visibility::Adjacent
+            },
+            occupy: self.occupy.unwrap_or(0),
+            vacate: self.vacate.unwrap_or(if self.in_sight { 5 } else { 4 }),
+        };
+
+        let mut simulator = input.simulator(rules);
+        match simulator.run() {
+            Outcome::Equilibrium { step } => println!(
+                "Equilibrium after {} steps, with {} occupied seats",
+                step,
+                simulator.occupied_seats()
+            ),
+            Outcome::Cycle { step, period } => println!(
+                "Cycle of period {} from step {}, with {} occupied seats at step {}",
+                period,
+                step,
+                simulator.occupied_seats(),
+                step + period
+            ),
+        }
+    }
 }
@@ -2,14 +2,21 @@
 
 use crate::solver::Solver;
 use std::{
+    fmt::{self, Display},
+    fs,
     io::{self, BufRead, BufReader},
-    ops::{AddAssign, Mul},
+    ops::{Add, AddAssign, Mul},
 };
 
 /// https://adventofcode.com/2020/day/12
-pub struct Day12;
+pub struct Day12 {
+    /// Print the ship's position after each instruction, in both navigation models.
+    pub log: bool,
+    /// Write the routes of both navigation models to this SVG file.
+    pub svg: Option<String>,
+}
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Point(i32, i32);
 
 #[derive(Copy, Clone)]
@@ -20,11 +27,23 @@ pub enum Direction {
     West,
 }
 
+/// How the instructions are interpreted.
+#[derive(Copy, Clone)]
+enum Model {
+    /// Actions N, S, E and W move the ship, and L, R and F turn or move it forward.
+    Heading,
+    /// Actions N, S, E, W, L and R move the waypoint, and F moves the ship towards it.
+    Waypoint,
+}
+
+#[derive(Copy, Clone)]
 pub struct Ship {
     position: Point,
-    direction: Direction,
+    /// Direction the ship faces (as a unit vector), or its waypoint (relative to it).
+    heading: Point,
 }
 
+#[derive(Copy, Clone)]
 pub enum Instruction {
     TurnLeft(usize),
     TurnRight(usize),
@@ -32,47 +51,134 @@ pub enum Instruction {
     Move(Direction, usize),
 }
 
+#[derive(Debug)]
+enum NavigationError {
+    /// The instruction at `line` turns by an angle which isn't a multiple of 90 degrees.
+    InvalidAngle { line: usize, angle: usize },
+}
+
+impl Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NavigationError::InvalidAngle { line, angle } => {
+                write!(f, "line {}: can't turn by {} degrees", line, angle)
+            }
+        }
+    }
+}
+
+/// Returns the ship after each of the `instructions` (and before the first one).
+fn navigate(instructions: &[Instruction], model: Model) -> Result<Vec<Ship>, NavigationError> {
+    let mut ship = Ship {
+        position: Point(0, 0),
+        heading: match model {
+            Model::Heading => Point(1, 0),
+            Model::Waypoint => Point(10, 1),
+        },
+    };
+    let mut route = vec![ship];
+
+    for (line, instruction) in (1..).zip(instructions) {
+        let invalid_angle = |angle| NavigationError::InvalidAngle { line, angle };
+        match (*instruction, model) {
+            (Instruction::TurnLeft(angle), _) => {
+                ship.heading = ship
+                    .heading
+                    .turn(-(angle as i32))
+                    .ok_or(invalid_angle(angle))?
+            }
+            (Instruction::TurnRight(angle), _) => {
+                ship.heading = ship
+                    .heading
+                    .turn(angle as i32)
+                    .ok_or(invalid_angle(angle))?
+            }
+            (Instruction::MoveForward(times), _) => ship.position += ship.heading * times,
+            (Instruction::Move(direction, value), Model::Heading) => {
+                ship.position += direction * value
+            }
+            (Instruction::Move(direction, value), Model::Waypoint) => {
+                ship.heading += direction * value
+            }
+        }
+        route.push(ship);
+    }
+
+    Ok(route)
+}
+
+/// Returns an SVG image of the routes (with the north up), where the waypoints
+/// are marked by dots.
+fn to_svg(heading_route: &[Ship], waypoint_route: &[Ship]) -> String {
+    let waypoints = waypoint_route
+        .iter()
+        .map(|ship| ship.position + ship.heading)
+        .collect::<Vec<_>>();
+    let points = || {
+        heading_route
+            .iter()
+            .chain(waypoint_route)
+            .map(|ship| ship.position)
+            .chain(waypoints.iter().copied())
+    };
+
+    let min_x = points().map(|Point(x, _)| x).min().unwrap();
+    let max_x = points().map(|Point(x, _)| x).max().unwrap();
+    let min_y = points().map(|Point(_, y)| y).min().unwrap();
+    let max_y = points().map(|Point(_, y)| y).max().unwrap();
+    let size = (max_x - min_x).max(max_y - min_y).max(1);
+    let margin = size / 20 + 1;
+
+    let polyline = |route: &[Ship], colour: &str| {
+        let points = route
+            .iter()
+            .map(|ship| format!("{},{}", ship.position.0, -ship.position.1))
+            .collect::<Vec<_>>();
+        format!(
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\" \
+             vector-effect=\"non-scaling-stroke\"/>\n",
+            points.join(" "),
+            colour
+        )
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"800\" height=\"800\">\n",
+        min_x - margin,
+        -max_y - margin,
+        max_x - min_x + 2 * margin,
+        max_y - min_y + 2 * margin
+    );
+    svg.push_str(&polyline(heading_route, "steelblue"));
+    svg.push_str(&polyline(waypoint_route, "darkorange"));
+    for Point(x, y) in waypoints {
+        svg.push_str(&format!(
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"crimson\"/>\n",
+            x,
+            -y,
+            size as f64 / 400.0
+        ));
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
 impl Solver for Day12 {
     type Input = Vec<Instruction>;
     type Output1 = i32;
     type Output2 = i32;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let mut ship = Ship {
-            direction: Direction::East,
-            position: Point(0, 0),
-        };
-
-        for instruction in input {
-            match *instruction {
-                Instruction::TurnLeft(angle) => ship.turn(-(angle as i32)),
-                Instruction::TurnRight(angle) => ship.turn(angle as i32),
-                Instruction::MoveForward(value) => ship.position += ship.direction * value,
-                Instruction::Move(direction, value) => ship.position += direction * value,
-            }
-        }
+        let route = navigate(input, Model::Heading).unwrap_or_else(|err| panic!("{}", err));
 
-        ship.position.0.abs() + ship.position.1.abs()
+        route.last().unwrap().position.manhattan_distance()
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let mut ship = Ship {
-            direction: Direction::East,
-            position: Point(0, 0),
-        };
+        let route = navigate(input, Model::Waypoint).unwrap_or_else(|err| panic!("{}", err));
 
-        let mut waypoint = Point(10, 1);
-
-        for instruction in input {
-            match *instruction {
-                Instruction::TurnLeft(angle) => waypoint.turn(-(angle as i32)),
-                Instruction::TurnRight(angle) => waypoint.turn(angle as i32),
-                Instruction::MoveForward(times) => ship.position += waypoint * times,
-                Instruction::Move(direction, value) => waypoint += direction * value,
-            }
-        }
-
-        ship.position.0.abs() + ship.position.1.abs()
+        route.last().unwrap().position.manhattan_distance()
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
@@ -95,34 +201,90 @@ impl Solver for Day12 {
             })
             .collect()
     }
-}
 
-fn mod4(x: i32) -> i32 {
-    ((x % 4) + 4) % 4
-}
+    fn report(&self, input: &Self::Input) {
+        if !self.log && self.svg.is_none() {
+            return;
+        }
+
+        let (heading_route, waypoint_route) = match (
+            navigate(input, Model::Heading),
+            navigate(input, Model::Waypoint),
+        ) {
+            (Ok(heading_route), Ok(waypoint_route)) => (heading_route, waypoint_route),
+            (Err(err), _) | (_, Err(err)) => return println!("{}", err),
+        };
+
+        if self.log {
+            println!(
+                "{:>5} {:<6} {:<32} {:<32}",
+                "Line", "Action", "Heading model", "Waypoint model"
+            );
+            for (line, instruction) in (1..).zip(input) {
+                let (heading, waypoint) = (heading_route[line], waypoint_route[line]);
+                println!(
+                    "{:>5} {:<6} {:<32} {:<32}",
+                    line,
+                    instruction.to_string(),
+                    format!("ship {} facing {}", heading.position, heading.heading),
+                    format!("ship {} waypoint {}", waypoint.position, waypoint.heading),
+                );
+            }
+        }
 
-impl Ship {
-    fn turn(&mut self, angle: i32) {
-        use Direction::*;
-        self.direction = [North, East, South, West][mod4(
-            match self.direction {
-                North => 0,
-                East => 1,
-                South => 2,
-                West => 3,
-            } + angle / 90,
-        ) as usize];
+        if let Some(path) = &self.svg {
+            fs::write(path, to_svg(&heading_route, &waypoint_route))
+                .expect("unable to write SVG file");
+        }
     }
 }
 
 impl Point {
-    fn turn(&mut self, angle: i32) {
-        *self = match mod4(angle / 90) {
+    /// Rotates the point around the origin by `angle` degrees clockwise (or
+    /// counterclockwise, if negative), if it's a multiple of 90.
+    fn turn(self, angle: i32) -> Option<Point> {
+        if angle % 90 != 0 {
+            return None;
+        }
+
+        Some(match (angle / 90).rem_euclid(4) {
+            0 => self,
             1 => Point(self.1, -self.0),
             2 => Point(-self.0, -self.1),
-            3 => Point(-self.1, self.0),
-            _ => unreachable!(),
-        };
+            _ => Point(-self.1, self.0),
+        })
+    }
+
+    fn manhattan_distance(self) -> i32 {
+        self.0.abs() + self.1.abs()
+    }
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("({}, {})", self.0, self.1))
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::TurnLeft(angle) => write!(f, "L{}", angle),
+            Instruction::TurnRight(angle) => write!(f, "R{}", angle),
+            Instruction::MoveForward(value) => write!(f, "F{}", value),
+            Instruction::Move(Direction::North, value) => write!(f, "N{}", value),
+            Instruction::Move(Direction::East, value) => write!(f, "E{}", value),
+            Instruction::Move(Direction::South, value) => write!(f, "S{}", value),
+            Instruction::Move(Direction::West, value) => write!(f, "W{}", value),
+        }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Point(self.0 + rhs.0, self.1 + rhs.1)
     }
 }
 
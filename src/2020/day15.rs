@@ -3,60 +3,95 @@
 use crate::solver::Solver;
 use std::{
     collections::HashMap,
-    io::{self, BufRead, BufReader},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    mem::replace,
 };
 
 /// https://adventofcode.com/2020/day/15
-pub struct Day15;
+pub struct Day15 {
+    /// Print the first this many numbers spoken.
+    pub prefix: Option<usize>,
+    /// Print the turn on which this number is first spoken (if it is, within `turns`).
+    pub first: Option<u32>,
+    /// Write the numbers spoken in the first `turns` turns to this file, one per line.
+    pub stream: Option<String>,
+    /// Number of turns to search for `first` or write to `stream` (defaults to 30,000,000).
+    pub turns: Option<usize>,
+}
 
-struct MemoryGame<'a>(&'a [usize]);
+/// Numbers below this are looked up in a flat table, and the (few) other ones in a map.
+const SMALL_NUMBERS: usize = 1 << 22;
 
-impl<'a> MemoryGame<'a> {
-    fn nth_number(&self, n: usize) -> usize {
-        let starting_numbers = self.0;
+/// Iterator over the numbers spoken (i.e. the Van Eck sequence of the starting numbers).
+pub struct MemoryGame<'a> {
+    starting_numbers: &'a [usize],
+    /// Number of turns that have been played.
+    turn: usize,
+    /// Number that will be spoken next (after the starting numbers).
+    next_number: u32,
+    /// Last turn (1-based, so that 0 means never) on which each small number was spoken.
+    last_turn_small: Vec<u32>,
+    /// Last turn on which each number that isn't small was spoken.
+    last_turn_large: HashMap<u32, u32>,
+}
 
-        if n < starting_numbers.len() {
-            return starting_numbers[n];
+impl<'a> MemoryGame<'a> {
+    fn new(starting_numbers: &'a [usize], small_numbers: usize) -> Self {
+        MemoryGame {
+            starting_numbers,
+            turn: 0,
+            next_number: 0,
+            last_turn_small: vec![0; small_numbers],
+            last_turn_large: HashMap::new(),
         }
+    }
 
-        let mut prev = HashMap::new();
-        let mut last = starting_numbers
-            .iter()
-            .enumerate()
-            .map(|(turn, &starting_number)| (starting_number, turn))
-            .collect::<HashMap<_, _>>();
-
-        let mut number = *starting_numbers.last().unwrap();
-
-        for turn in starting_numbers.len()..n {
-            number = match prev.get(&number) {
-                None => 0,
-                Some(&prev_turn) => {
-                    let &last_turn = last.get(&number).unwrap();
-                    last_turn - prev_turn
-                }
-            };
-
-            if let Some(last_turn) = last.insert(number, turn) {
-                prev.insert(number, last_turn);
-            }
-        }
+    /// Returns the `n`-th number spoken (1-based).
+    fn nth_number(starting_numbers: &[usize], n: usize) -> u32 {
+        // Every number spoken (except the starting ones) is smaller than `n`.
+        MemoryGame::new(starting_numbers, n.min(SMALL_NUMBERS))
+            .nth(n - 1)
+            .unwrap()
+    }
+}
+
+impl Iterator for MemoryGame<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let number = match self.starting_numbers.get(self.turn) {
+            Some(&starting_number) => starting_number as u32,
+            None => self.next_number,
+        };
+
+        self.turn += 1;
+        let turn = self.turn as u32;
 
-        number
+        let last_turn = match self.last_turn_small.get_mut(number as usize) {
+            Some(last_turn) => replace(last_turn, turn),
+            None => self.last_turn_large.insert(number, turn).unwrap_or(0),
+        };
+
+        // If that was the first time the number has been spoken, the next number is 0.
+        // Otherwise, it's how many turns apart the number is from when it was previously spoken.
+        self.next_number = if last_turn == 0 { 0 } else { turn - last_turn };
+
+        Some(number)
     }
 }
 
 impl Solver for Day15 {
     type Input = Vec<usize>;
-    type Output1 = usize;
-    type Output2 = usize;
+    type Output1 = u32;
+    type Output2 = u32;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        MemoryGame(input).nth_number(2020)
+        MemoryGame::nth_number(input, 2020)
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        MemoryGame(input).nth_number(30000000)
+        MemoryGame::nth_number(input, 30000000)
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
@@ -69,4 +104,33 @@ impl Solver for Day15 {
             .map(|starting_number| starting_number.parse().unwrap())
             .collect()
     }
+
+    fn report(&self, input: &Self::Input) {
+        let turns = self.turns.unwrap_or(30000000);
+
+        if let Some(prefix) = self.prefix {
+            let numbers = MemoryGame::new(input, prefix.min(SMALL_NUMBERS))
+                .take(prefix)
+                .map(|number| number.to_string())
+                .collect::<Vec<_>>();
+            println!("{}", numbers.join(","));
+        }
+
+        if let Some(first) = self.first {
+            match MemoryGame::new(input, turns.min(SMALL_NUMBERS))
+                .take(turns)
+                .position(|number| number == first)
+            {
+                Some(turn) => println!("{} is first spoken on turn {}", first, turn + 1),
+                None => println!("{} isn't spoken in the first {} turns", first, turns),
+            }
+        }
+
+        if let Some(path) = &self.stream {
+            let mut file = BufWriter::new(File::create(path).expect("unable to create file"));
+            for number in MemoryGame::new(input, turns.min(SMALL_NUMBERS)).take(turns) {
+                writeln!(file, "{}", number).expect("unable to write to file");
+            }
+        }
+    }
 }
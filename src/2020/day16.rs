@@ -1,6 +1,6 @@
 //! --- Day 16: Ticket Translation ---
 
-use crate::solver::Solver;
+use crate::{assignment::unique_assignment, solver::Solver};
 use lazy_static::lazy_static;
 use std::{io, ops::RangeInclusive};
 
@@ -49,37 +49,25 @@ impl Solver for Day16 {
                 .filter(|t| t.iter().all(|v| has_valid(v, fields))),
         );
 
-        // For each ticket value, store the possible field indices as a bitmask.
-        let mut possible_fields_per_value = (0..my_ticket.len())
+        // For each ticket value, store the indices of the fields it could belong to.
+        let possible_fields_per_value = (0..my_ticket.len())
             .map(|v_i| {
-                (0..fields.len()).fold(0u64, |possible_fields, f_i| {
-                    let is_possible = tickets.iter().all(|t| is_valid(&t[v_i], &fields[f_i]));
-                    possible_fields | ((is_possible as u64) << f_i)
-                })
+                (0..fields.len())
+                    .filter(|&f_i| tickets.iter().all(|t| is_valid(&t[v_i], &fields[f_i])))
+                    .collect()
             })
-            .collect::<Vec<u64>>();
+            .collect::<Vec<Vec<usize>>>();
 
         // For each ticket value, store its corresponding field index.
-        let mut correct_field_per_value = vec![None; my_ticket.len()];
-        while let Some(v_i) = possible_fields_per_value
-            .iter()
-            .position(|possible_fields| possible_fields.count_ones() == 1)
-        {
-            let correct_field = possible_fields_per_value[v_i];
-            let f_i = correct_field.trailing_zeros() as usize;
-            correct_field_per_value[v_i] = Some(f_i);
-            for possible_fields in possible_fields_per_value.iter_mut() {
-                *possible_fields &= !correct_field;
-            }
-        }
-        /* assert!(correct_field_per_value.iter().all(|f_i| f_i.is_some())); */
+        let correct_field_per_value =
+            unique_assignment(&possible_fields_per_value, fields.len()).unwrap();
 
         // Multiply together the values in my ticket of "departure" fields.
         my_ticket
             .iter()
             .enumerate()
             .filter(|&(v_i, _)| {
-                let f_i = correct_field_per_value[v_i].unwrap();
+                let f_i = correct_field_per_value[v_i];
                 fields[f_i].0.starts_with("departure")
             })
             .map(|(_, v)| *v)
@@ -1,6 +1,6 @@
 //! --- Day 21: Allergen Assessment ---
 
-use crate::solver::Solver;
+use crate::{assignment::unique_assignment, solver::Solver};
 use std::{
     collections::{HashMap, HashSet},
     io::{self, BufRead, BufReader},
@@ -67,35 +67,36 @@ impl Solver for Day21 {
         let (foods, _) = input;
 
         // For each allergen, map the ingredients which possibly contain it.
-        let mut allergen_possibilities = allergen_possibilities(foods);
-
-        // For each allergen, map the ingredient which actually contains it.
-        let mut allergen_ingredient = HashMap::new();
-
-        while !allergen_possibilities.is_empty() {
-            let (allergen, ingredient) = allergen_possibilities
-                .iter()
-                .find(|(_, ingredients_with_allergen)| ingredients_with_allergen.len() == 1)
-                .map(|(allergen, ingredient)| (allergen, ingredient.iter().next().unwrap()))
-                .map(|(allergen, ingredient)| (allergen.clone(), ingredient.clone()))
-                .unwrap();
-
-            allergen_possibilities.remove(&allergen);
-            for (_, ingredients_with_allergen) in allergen_possibilities.iter_mut() {
-                ingredients_with_allergen.remove(&ingredient);
-            }
+        let allergen_possibilities = allergen_possibilities(foods);
 
-            allergen_ingredient.insert(allergen, ingredient);
-        }
+        // Arrange the allergens alphabetically, so that the ingredients which actually
+        // contain them are already in the order of the canonical dangerous ingredient list.
+        let mut allergens = allergen_possibilities.keys().collect::<Vec<_>>();
+        allergens.sort();
 
-        // Arrange the ingredients alphabetically by their allergen and separate them by commas
-        // (with no spaces) to produce your canonical dangerous ingredient list.
-        let mut allergen_ingredient = allergen_ingredient.into_iter().collect::<Vec<_>>();
-        allergen_ingredient.sort_by_key(|(allergen, _)| allergen.clone());
+        let ingredients = allergen_possibilities
+            .values()
+            .flatten()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let candidates = allergens
+            .iter()
+            .map(|&allergen| {
+                let ingredients_with_allergen = &allergen_possibilities[allergen];
+                (0..ingredients.len())
+                    .filter(|&i| ingredients_with_allergen.contains(ingredients[i]))
+                    .collect()
+            })
+            .collect::<Vec<_>>();
 
-        allergen_ingredient
+        // For each allergen, find the ingredient which actually contains it, and separate
+        // them by commas (with no spaces) to produce your canonical dangerous ingredient list.
+        unique_assignment(&candidates, ingredients.len())
+            .unwrap()
             .into_iter()
-            .map(|(_, ingredient)| ingredient)
+            .map(|i| ingredients[i].as_str())
             .collect::<Vec<_>>()
             .join(",")
     }
@@ -2,28 +2,168 @@
 
 use crate::solver::Solver;
 use std::{
-    collections::{hash_map::DefaultHasher, HashSet, VecDeque},
-    hash::{Hash, Hasher},
-    io,
+    collections::{HashMap, HashSet, VecDeque},
+    fs, io,
+    iter::once,
 };
 
 /// https://adventofcode.com/2020/day/22
-pub struct Day22;
+pub struct Day22 {
+    /// Print the replay log of Recursive Combat.
+    pub replay: bool,
+    /// Write the replay log of Recursive Combat to this file.
+    pub export: Option<String>,
+    /// Print statistics on the games of Recursive Combat.
+    pub stats: bool,
+}
+
+type Deck = VecDeque<u8>;
+
+/// Encodes both decks exactly, using 0 (which isn't a card) to separate them.
+fn encode(player1: &Deck, player2: &Deck) -> Vec<u8> {
+    player1
+        .iter()
+        .chain(once(&0))
+        .chain(player2)
+        .copied()
+        .collect()
+}
+
+fn score(deck: &Deck) -> usize {
+    deck.iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &card)| (i + 1) * card as usize)
+        .sum()
+}
+
+fn deck_to_string(deck: &Deck) -> String {
+    deck.iter()
+        .map(|card| card.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Default)]
+pub struct Stats {
+    /// Number of games played (including the first one), not counting memoized ones.
+    games: usize,
+    /// Number of rounds played, across all games.
+    rounds: usize,
+    /// Number of sub-games whose winner was already known from an identical one.
+    memoized: usize,
+    /// Deepest level of recursion reached (the first game is at depth 1).
+    max_depth: usize,
+}
+
+#[derive(Default)]
+pub struct RecursiveCombat {
+    /// Winner of each (sub-)game that has been played, keyed by its starting decks.
+    winners: HashMap<Vec<u8>, u8>,
+    /// Replay of every round that has been played, if it's being logged.
+    log: Option<Vec<String>>,
+    stats: Stats,
+}
+
+impl RecursiveCombat {
+    fn with_log() -> Self {
+        RecursiveCombat {
+            log: Some(vec![]),
+            ..Default::default()
+        }
+    }
+
+    fn log(&mut self, line: impl FnOnce() -> String) {
+        if let Some(log) = self.log.as_mut() {
+            log.push(line());
+        }
+    }
+
+    /// Plays a game with the given decks until it's over, and returns its winner.
+    fn play(&mut self, player1: &mut Deck, player2: &mut Deck, depth: usize) -> u8 {
+        self.stats.games += 1;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+
+        let game = self.stats.games;
+        let starting_decks = encode(player1, player2);
+        let mut rounds = HashSet::new();
+
+        self.log(|| format!("=== Game {} ===\n", game));
+
+        let winner = loop {
+            if player1.is_empty() || player2.is_empty() {
+                break if player1.is_empty() { 2 } else { 1 };
+            }
+
+            if !rounds.insert(encode(player1, player2)) {
+                self.log(|| "This exact round had already been played!".to_string());
+                break 1;
+            }
+
+            let round = rounds.len();
+            self.stats.rounds += 1;
+            self.log(|| {
+                format!(
+                    "-- Round {} (Game {}) --\nPlayer 1's deck: {}\nPlayer 2's deck: {}",
+                    round,
+                    game,
+                    deck_to_string(player1),
+                    deck_to_string(player2)
+                )
+            });
+
+            let card1 = player1.pop_front().unwrap();
+            let card2 = player2.pop_front().unwrap();
+            self.log(|| format!("Player 1 plays: {}\nPlayer 2 plays: {}", card1, card2));
+
+            let winner = if player1.len() < card1 as usize || player2.len() < card2 as usize {
+                if card1 > card2 {
+                    1
+                } else {
+                    2
+                }
+            } else {
+                let mut sub_player1 = player1.iter().take(card1 as usize).copied().collect();
+                let mut sub_player2 = player2.iter().take(card2 as usize).copied().collect();
+                let sub_decks = encode(&sub_player1, &sub_player2);
+
+                if let Some(&winner) = self.winners.get(&sub_decks) {
+                    self.stats.memoized += 1;
+                    self.log(|| format!("The winner of this sub-game is known: {}", winner));
+                    winner
+                } else {
+                    self.log(|| "Playing a sub-game to determine the winner...\n".to_string());
+                    let winner = self.play(&mut sub_player1, &mut sub_player2, depth + 1);
+                    self.log(|| format!("...anyway, back to game {}.", game));
+                    winner
+                }
+            };
+
+            self.log(|| format!("Player {} wins round {} of game {}!\n", winner, round, game));
 
-fn hash(player1: &VecDeque<u8>, player2: &VecDeque<u8>) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    (player1, player2).hash(&mut hasher);
-    hasher.finish()
+            if winner == 1 {
+                player1.push_back(card1);
+                player1.push_back(card2);
+            } else {
+                player2.push_back(card2);
+                player2.push_back(card1);
+            }
+        };
+
+        self.log(|| format!("The winner of game {} is player {}!\n", game, winner));
+        self.winners.insert(starting_decks, winner);
+
+        winner
+    }
 }
 
 impl Solver for Day22 {
-    type Input = (VecDeque<u8>, VecDeque<u8>);
+    type Input = (Deck, Deck);
     type Output1 = usize;
     type Output2 = usize;
 
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
         let (mut player1, mut player2) = input.clone();
-        let card_count = player1.len() + player2.len();
 
         while !(player1.is_empty() || player2.is_empty()) {
             let card1 = player1.pop_front().unwrap();
@@ -38,59 +178,19 @@ impl Solver for Day22 {
             }
         }
 
-        if player1.is_empty() { player2 } else { player1 }
-            .iter()
-            .enumerate()
-            .map(|(i, &card)| (card_count - i) * card as usize)
-            .sum()
+        score(if player1.is_empty() {
+            &player2
+        } else {
+            &player1
+        })
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
         let (mut player1, mut player2) = input.clone();
-        let card_count = player1.len() + player2.len();
-
-        #[rustfmt::skip]
-        fn recursive_combat_winner(player1: &mut VecDeque<u8>, player2: &mut VecDeque<u8>) -> u8 {
-            let mut rounds = HashSet::new();
-
-            while !(player1.is_empty() || player2.is_empty()) {
-                if !rounds.insert(hash(player1, player2)) {
-                    return 1; // this exact round had already been played
-                }
-
-                let card1 = player1.pop_front().unwrap();
-                let card2 = player2.pop_front().unwrap();
-
-                let winner = if player1.len() < card1 as usize
-                             || player2.len() < card2 as usize
-                {
-                    if card1 > card2 { 1 } else { 2 }
-                } else {
-                    recursive_combat_winner(
-                        &mut player1.iter().take(card1 as usize).cloned().collect(),
-                        &mut player2.iter().take(card2 as usize).cloned().collect(),
-                    )
-                };
-
-                if winner == 1 {
-                    player1.push_back(card1);
-                    player1.push_back(card2);
-                } else {
-                    player2.push_back(card2);
-                    player2.push_back(card1);
-                }
-            }
 
-            if player1.is_empty() { 2 } else { 1 }
-        }
+        let winner = RecursiveCombat::default().play(&mut player1, &mut player2, 1);
 
-        let winner = recursive_combat_winner(&mut player1, &mut player2);
-
-        if winner == 1 { player1 } else { player2 }
-            .iter()
-            .enumerate()
-            .map(|(i, &card)| (card_count - i) * card as usize)
-            .sum()
+        score(if winner == 1 { &player1 } else { &player2 })
     }
 
     fn parse_input<R: io::Read>(&self, mut r: R) -> Self::Input {
@@ -105,9 +205,43 @@ impl Solver for Day22 {
                 .lines()
                 .skip(1)
                 .map(|card| card.parse::<u8>().unwrap())
-                .collect::<VecDeque<_>>()
+                .collect::<Deck>()
         };
 
         (parse_player(), parse_player())
     }
+
+    fn report(&self, input: &Self::Input) {
+        if !(self.replay || self.export.is_some() || self.stats) {
+            return;
+        }
+
+        let (mut player1, mut player2) = input.clone();
+
+        let mut game = if self.replay || self.export.is_some() {
+            RecursiveCombat::with_log()
+        } else {
+            RecursiveCombat::default()
+        };
+        let winner = game.play(&mut player1, &mut player2, 1);
+
+        if let Some(log) = game.log.as_ref().map(|log| log.join("\n")) {
+            if self.replay {
+                println!("{}", log);
+                println!("== Post-game results ==");
+                println!("Player 1's deck: {}", deck_to_string(&player1));
+                println!("Player 2's deck: {}", deck_to_string(&player2));
+            }
+            if let Some(path) = &self.export {
+                fs::write(path, log).expect("unable to write replay log");
+            }
+        }
+        if self.stats {
+            let stats = &game.stats;
+            println!("Player {} wins after {} game(s)", winner, stats.games);
+            println!("Rounds played: {}", stats.rounds);
+            println!("Memoized sub-games: {}", stats.memoized);
+            println!("Deepest recursion: {}", stats.max_depth);
+        }
+    }
 }
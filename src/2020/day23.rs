@@ -3,69 +3,170 @@
 use crate::solver::Solver;
 use std::{
     io::{self, BufRead, BufReader},
-    iter::{once, FromIterator},
+    iter::successors,
 };
 
 /// https://adventofcode.com/2020/day/23
-pub struct Day23;
+pub struct Day23 {
+    /// Total number of cups to play with (defaults to part 2's one million).
+    pub cups: Option<usize>,
+    /// Number of moves to make (defaults to part 2's ten million).
+    pub moves: Option<usize>,
+    /// Number of cups picked up on each move (defaults to 3).
+    pub pick_up: Option<usize>,
+    /// Print a snapshot of the cups every this many moves.
+    pub snapshot_every: Option<usize>,
+}
+
+/// Integer type used to store cup labels.
+pub trait Label: Copy + Eq {
+    fn from_usize(n: usize) -> Self;
+    fn to_usize(self) -> usize;
+}
+
+impl Label for u8 {
+    fn from_usize(n: usize) -> Self {
+        n as u8
+    }
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl Label for u32 {
+    fn from_usize(n: usize) -> Self {
+        n as u32
+    }
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Cups<T> {
-    label: Vec<T>,
-    count: usize,
-    prev: Vec<usize>,
-    next: Vec<usize>,
+    /// Label of the cup immediately clockwise of each cup, indexed by
+    /// its label (since labels go from 1 up to the number of cups).
+    next: Vec<T>,
+    /// Label of the current cup.
+    current: T,
+    /// Number of cups picked up on each move.
+    pick_up_count: usize,
 }
 
-impl Solver for Day23 {
-    type Input = Cups<u8>;
-    type Output1 = String;
-    type Output2 = usize;
+impl<T: Label> Cups<T> {
+    /// Places the cups labeled with `labels` in a circle, followed by the next
+    /// highest labels until there are `cup_count` cups in total.
+    pub fn new(labels: &[u8], cup_count: usize, pick_up_count: usize) -> Self {
+        assert!(labels.len() <= cup_count);
+        assert!(0 < pick_up_count && pick_up_count < cup_count);
+        assert_eq!(
+            T::from_usize(cup_count).to_usize(),
+            cup_count,
+            "labels must fit in the label type"
+        );
+
+        let labels = labels
+            .iter()
+            .map(|&label| label as usize)
+            .chain(labels.len() + 1..=cup_count)
+            .collect::<Vec<_>>();
+
+        let mut next = vec![T::from_usize(0); cup_count + 1];
+        for (i, &label) in labels.iter().enumerate() {
+            next[label] = T::from_usize(labels[(i + 1) % cup_count]);
+        }
 
-    fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let mut cups = input.clone();
-        let mut curr = 0; // index of the current cup
-
-        for _ in 1..=100 {
-            let (pick_ups, pick_up_labels, next) = {
-                let fst = cups.next[curr];
-                let snd = cups.next[fst];
-                let trd = cups.next[snd];
-                (
-                    (fst, snd, trd),
-                    [cups.label[fst], cups.label[snd], cups.label[trd]],
-                    cups.next[trd],
-                )
-            };
+        Cups {
+            next,
+            current: T::from_usize(labels[0]),
+            pick_up_count,
+        }
+    }
+
+    fn cup_count(&self) -> usize {
+        self.next.len() - 1
+    }
+
+    fn next_of(&self, label: T) -> T {
+        self.next[label.to_usize()]
+    }
 
-            // Remove the three picked up cups from the circle.
-            cups.link(curr, next);
+    /// Makes a single move, using `pick_ups` as a buffer for the picked up cups.
+    fn make_move(&mut self, pick_ups: &mut Vec<T>) {
+        let current = self.current;
 
-            // Select the destination cup.
-            let curr_label = cups.label[curr];
-            let mut dest_label = match curr_label - 1 {
-                0 => cups.count as u8, // highest label
+        // Pick up the cups immediately clockwise of the current cup.
+        pick_ups.clear();
+        let mut last_pick_up = current;
+        for _ in 0..self.pick_up_count {
+            last_pick_up = self.next_of(last_pick_up);
+            pick_ups.push(last_pick_up);
+        }
+
+        // Remove the picked up cups from the circle.
+        let next = self.next_of(last_pick_up);
+        self.next[current.to_usize()] = next;
+
+        // Select the destination cup.
+        let mut dest = current.to_usize();
+        let dest = loop {
+            dest = match dest - 1 {
+                0 => self.cup_count(), // highest label
                 l => l,
             };
-            while pick_up_labels.contains(&dest_label) {
-                dest_label = match dest_label - 1 {
-                    0 => cups.count as u8,
-                    l => l,
-                };
+            if !pick_ups.contains(&T::from_usize(dest)) {
+                break T::from_usize(dest);
             }
-            let dest = cups.find(dest_label).unwrap();
+        };
 
-            // Places the pick ups immediately clockwise of the destination cup.
-            cups.link(pick_ups.2, cups.next[dest]);
-            cups.link(dest, pick_ups.0);
+        // Places the pick ups immediately clockwise of the destination cup.
+        self.next[last_pick_up.to_usize()] = self.next_of(dest);
+        self.next[dest.to_usize()] = pick_ups[0];
+
+        // Select the new current cup.
+        self.current = next;
+    }
 
-            // Select the new current cup.
-            curr = next;
+    /// Makes `moves` moves.
+    pub fn play(&mut self, moves: usize) {
+        self.play_with_snapshots(moves, moves.max(1), |_, _| {});
+    }
+
+    /// Makes `moves` moves, calling `snapshot` after every `every` of them.
+    pub fn play_with_snapshots(
+        &mut self,
+        moves: usize,
+        every: usize,
+        mut snapshot: impl FnMut(usize, &Self),
+    ) {
+        assert!(every > 0, "snapshots must be at least one move apart");
+        let mut pick_ups = Vec::with_capacity(self.pick_up_count);
+
+        for move_count in 1..=moves {
+            self.make_move(&mut pick_ups);
+            if move_count % every == 0 {
+                snapshot(move_count, self);
+            }
         }
+    }
+
+    /// Returns the cup labels in clockwise order, starting with the cup labeled `label`.
+    pub fn clockwise_from(&self, label: T) -> impl Iterator<Item = T> + '_ {
+        successors(Some(label), move |&label| Some(self.next_of(label))).take(self.cup_count())
+    }
+}
+
+impl Solver for Day23 {
+    type Input = Vec<u8>;
+    type Output1 = String;
+    type Output2 = usize;
+
+    fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
+        let mut cups = Cups::<u8>::new(input, input.len(), 3);
+        cups.play(100);
 
         // Starting after the cup labeled 1, collect the other cups' labels clockwise.
-        cups.clockwise_from_label(1)
-            .into_iter()
+        cups.clockwise_from(1)
             .skip(1)
             .map(|label| label.to_string())
             .collect::<Vec<_>>()
@@ -73,56 +174,15 @@ impl Solver for Day23 {
     }
 
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let mut cups = Cups::from_labels_up_to(&input.label, 1_000_000);
-        let mut curr = 0; // index of the current cup
-
-        for _ in 1..=10_000_000 {
-            let (pick_ups, pick_up_labels, next) = {
-                let fst = cups.next[curr];
-                let snd = cups.next[fst];
-                let trd = cups.next[snd];
-                (
-                    (fst, snd, trd),
-                    [cups.label[fst], cups.label[snd], cups.label[trd]],
-                    cups.next[trd],
-                )
-            };
-
-            // Remove the three picked up cups from the circle.
-            cups.link(curr, next);
-
-            // Select the destination cup.
-            let curr_label = cups.label[curr];
-            let mut dest_label = match curr_label - 1 {
-                0 => cups.count, // highest label
-                l => l,
-            };
-            while pick_up_labels.contains(&dest_label) {
-                dest_label = match dest_label - 1 {
-                    0 => cups.count,
-                    l => l,
-                };
-            }
-            let dest = if dest_label <= 10 {
-                cups.find(dest_label).unwrap()
-            } else {
-                dest_label - 1
-            };
-
-            // Places the pick ups immediately clockwise of the destination cup.
-            cups.link(pick_ups.2, cups.next[dest]);
-            cups.link(dest, pick_ups.0);
-
-            // Select the new current cup.
-            curr = next;
-        }
+        let mut cups = Cups::<u32>::new(input, 1_000_000, 3);
+        cups.play(10_000_000);
 
         // Determine which two cups will end up immediately clockwise of cup 1.
         // What do you get if you multiply their labels together?
-        cups.clockwise_from_label(1)
-            .into_iter()
+        cups.clockwise_from(1)
             .skip(1)
             .take(2)
+            .map(|label| label as usize)
             .product()
     }
 
@@ -136,81 +196,37 @@ impl Solver for Day23 {
             .map(|cup| cup as u8 - b'0')
             .collect()
     }
-}
-
-impl<T: Copy + Eq> Cups<T> {
-    /// Places `this` before `that` (and so, `that` after `this`).
-    fn link(&mut self, this: usize, that: usize) {
-        self.next[this] = that;
-        self.prev[that] = this;
-    }
-
-    /// Returns the index of the cup labeled with `value`.
-    fn find(&self, value: T) -> Option<usize> {
-        self.label.iter().enumerate().find_map(
-            |(i, &label)| {
-                if label == value {
-                    Some(i)
-                } else {
-                    None
-                }
-            },
-        )
-    }
 
-    /// Returns the cup labels in clockwise order, starting with
-    /// the cup labeled `value`.
-    fn clockwise_from_label(&self, value: T) -> Vec<T> {
-        let mut labels = Vec::with_capacity(self.count - 1);
-        let mut cup = self.find(value).unwrap();
-        let first = cup;
-        loop {
-            labels.push(self.label[cup]);
-            cup = self.next[cup];
-            if cup == first {
-                break;
-            }
+    fn report(&self, input: &Self::Input) {
+        if self.cups.is_none()
+            && self.moves.is_none()
+            && self.pick_up.is_none()
+            && self.snapshot_every.is_none()
+        {
+            return;
         }
 
-        labels
-    }
-}
-
-impl<T> FromIterator<T> for Cups<T> {
-    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let label = iter.into_iter().collect::<Vec<_>>();
-        let count = label.len();
-
-        Cups {
-            label,
-            count,
-            prev: once(count - 1).chain(0..count - 1).collect(),
-            next: (1..count).chain(once(0)).collect(),
-        }
-    }
-}
+        let cup_count = self.cups.unwrap_or(1_000_000);
+        let moves = self.moves.unwrap_or(10_000_000);
+        let every = self.snapshot_every.unwrap_or(moves);
 
-impl Cups<usize> {
-    fn from_labels_up_to<T>(labels: &[T], count: usize) -> Self
-    where
-        T: Copy + Ord + Into<usize>,
-    {
-        let mut label = Vec::with_capacity(count);
-
-        for value in labels {
-            label.push((*value).into());
-        }
-
-        let highest_label: usize = (*labels.iter().max().unwrap()).into();
-        for value in (highest_label + 1)..=count {
-            label.push(value);
-        }
+        let describe = |cups: &Cups<u32>| {
+            let labels = |cups: &mut dyn Iterator<Item = u32>| {
+                cups.map(|label| label.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            if cup_count <= 20 {
+                format!("cups: {}", labels(&mut cups.clockwise_from(cups.current)))
+            } else {
+                let after_1 = labels(&mut cups.clockwise_from(1).skip(1).take(2));
+                format!("current: {}, after 1: {}", cups.current, after_1)
+            }
+        };
 
-        Cups {
-            label,
-            count,
-            prev: once(count - 1).chain(0..count - 1).collect(),
-            next: (1..count).chain(once(0)).collect(),
-        }
+        let mut cups = Cups::<u32>::new(input, cup_count, self.pick_up.unwrap_or(3));
+        cups.play_with_snapshots(moves, every, |move_count, cups| {
+            println!("-- move {} -- {}", move_count, describe(cups));
+        });
     }
 }
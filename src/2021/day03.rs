@@ -1,57 +1,128 @@
 //! --- Day 3: Binary Diagnostic ---
 
-use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use crate::{bigint::BigUint, solver::Solver};
+use std::{
+    cmp::Ordering,
+    io::{self, BufRead, BufReader},
+};
 
 /// https://adventofcode.com/2021/day/3
-pub struct Day03;
+pub struct Day03 {
+    /// Bit considered most common when 0 and 1 are equally common (defaults to 1).
+    pub tie_most: Option<u8>,
+    /// Bit considered least common when 0 and 1 are equally common (defaults to 0).
+    pub tie_least: Option<u8>,
+    /// Print the numbers kept at each step of finding the ratings.
+    pub trace: bool,
+}
 
-impl Solver for Day03 {
-    type Input = (Vec<u16>, u8);
-    type Output1 = u32;
-    type Output2 = u32;
+/// Bits of the diagnostic report, packed column by column, so that each column is a
+/// sequence of words which can be counted and filtered with bitwise operations.
+pub struct BitMatrix {
+    rows: usize,
+    columns: usize,
+    words_per_column: usize,
+    bits: Vec<u64>,
+}
 
-    /// Use the binary numbers in your diagnostic report to calculate the gamma rate
-    /// and epsilon rate, then multiply them together. What is the power consumption
-    /// of the submarine? (Be sure to represent your answer in decimal, not binary.)
-    fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let (numbers, bit_count) = input;
-        let (gamma_rate, epsilon_rate) = compute_rates(numbers, *bit_count);
+/// Set of rows of a `BitMatrix`, as a mask of the words of its columns.
+type Rows = Vec<u64>;
+
+fn count(rows: &[u64]) -> usize {
+    rows.iter().map(|word| word.count_ones() as usize).sum()
+}
 
-        gamma_rate as u32 * epsilon_rate as u32
+impl BitMatrix {
+    fn from_lines(lines: &[String]) -> Self {
+        let columns = lines.first().map_or(0, |line| line.len());
+        let words_per_column = lines.len().div_ceil(64);
+        let mut bits = vec![0; columns * words_per_column];
+
+        for (row, line) in lines.iter().enumerate() {
+            assert_eq!(
+                line.len(),
+                columns,
+                "line {} has a different width",
+                row + 1
+            );
+            for (col, bit) in line.chars().enumerate() {
+                match bit {
+                    '0' => {}
+                    '1' => bits[col * words_per_column + row / 64] |= 1 << (row % 64),
+                    _ => panic!("invalid bit on line {}: {:?}", row + 1, bit),
+                }
+            }
+        }
+
+        BitMatrix {
+            rows: lines.len(),
+            columns,
+            words_per_column,
+            bits,
+        }
     }
 
-    /// Use the binary numbers in your diagnostic report to calculate the oxygen generator
-    /// rating and CO2 scrubber rating, then multiply them together.
-    /// What is the life support rating of the submarine?
-    fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let (numbers, bit_count) = input;
-        let (oxygen_generator_rating, co2_scrubber_rating) = compute_ratings(numbers, *bit_count);
+    fn column(&self, col: usize) -> &[u64] {
+        &self.bits[col * self.words_per_column..(col + 1) * self.words_per_column]
+    }
 
-        oxygen_generator_rating as u32 * co2_scrubber_rating as u32
+    fn bit(&self, row: usize, col: usize) -> bool {
+        self.column(col)[row / 64] >> (row % 64) & 1 == 1
     }
 
-    fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
-        let lines = BufReader::new(r).lines().flatten().collect::<Vec<String>>();
-        let bit_count = lines.first().unwrap().len() as u8;
-
-        (
-            lines
-                .into_iter()
-                .map(|line| u16::from_str_radix(&line, 2).unwrap())
-                .collect::<Vec<u16>>(),
-            bit_count,
-        )
+    fn all_rows(&self) -> Rows {
+        (0..self.words_per_column)
+            .map(|i| match self.rows - 64 * i {
+                rows if rows >= 64 => u64::MAX,
+                rows => (1 << rows) - 1,
+            })
+            .collect()
     }
-}
 
-fn compute_bit_counts(numbers: &[u16], bit_shift: u8) -> (u16, u16) {
-    numbers.iter().fold((0, 0), |(count_0s, count_1s), n| {
-        if n & (1 << bit_shift) == 0 {
-            (count_0s + 1, count_1s)
-        } else {
-            (count_0s, count_1s + 1)
+    /// Returns the rows with `bit` in `col`, among `rows`.
+    fn filter(&self, rows: &[u64], col: usize, bit: bool) -> Rows {
+        rows.iter()
+            .zip(self.column(col))
+            .map(|(&rows, &ones)| if bit { rows & ones } else { rows & !ones })
+            .collect()
+    }
+
+    /// Returns the most and least common bits in `col`, among `rows`.
+    fn common_bits(&self, rows: &[u64], col: usize, tie_break: TieBreak) -> (bool, bool) {
+        let ones = count(&self.filter(rows, col, true));
+        let zeros = count(rows) - ones;
+
+        match ones.cmp(&zeros) {
+            Ordering::Less => (false, true),
+            Ordering::Equal => (tie_break.most_common, tie_break.least_common),
+            Ordering::Greater => (true, false),
         }
+    }
+
+    fn row(&self, row: usize) -> String {
+        (0..self.columns)
+            .map(|col| if self.bit(row, col) { '1' } else { '0' })
+            .collect()
+    }
+}
+
+/// Bits picked as most and least common when 0 and 1 are equally common.
+#[derive(Clone, Copy)]
+struct TieBreak {
+    most_common: bool,
+    least_common: bool,
+}
+
+/// A rating, along with the column, bit and number of rows kept at each step of
+/// finding it.
+struct Rating {
+    row: usize,
+    trace: Vec<(usize, bool, usize)>,
+}
+
+fn from_bits(bits: impl Iterator<Item = bool>) -> BigUint {
+    bits.fold(BigUint::default(), |n, bit| {
+        &n * &BigUint::from(2) + &BigUint::from(bit as u64)
     })
 }
 
@@ -59,21 +130,16 @@ fn compute_bit_counts(numbers: &[u16], bit_shift: u8) -> (u16, u16) {
 /// in the corresponding position of all numbers in the diagnostic report.
 /// The epsilon rate is calculated in a similar way; rather than use the
 /// most common bit, the least common bit from each position is used.
-fn compute_rates(numbers: &[u16], bit_count: u8) -> (u16, u16) {
-    let mut gamma_rate = 0;
-    let mut epsilon_rate = 0;
-
-    // @Speed: this loop could be done in parallel, as one bit lane doesn't depend on others.
-    for bit_shift in 0..bit_count {
-        let (count_0s, count_1s) = compute_bit_counts(numbers, bit_shift);
-        match Ord::cmp(&count_0s, &count_1s) {
-            std::cmp::Ordering::Less => gamma_rate |= 1 << bit_shift,
-            std::cmp::Ordering::Equal => unreachable!(),
-            std::cmp::Ordering::Greater => epsilon_rate |= 1 << bit_shift,
-        }
-    }
+fn compute_rates(report: &BitMatrix, tie_break: TieBreak) -> (BigUint, BigUint) {
+    let rows = report.all_rows();
+    let (gamma_bits, epsilon_bits): (Vec<_>, Vec<_>) = (0..report.columns)
+        .map(|col| report.common_bits(&rows, col, tie_break))
+        .unzip();
 
-    (gamma_rate, epsilon_rate)
+    (
+        from_bits(gamma_bits.into_iter()),
+        from_bits(epsilon_bits.into_iter()),
+    )
 }
 
 /// Start with the full list of binary numbers from your diagnostic report and
@@ -90,46 +156,105 @@ fn compute_rates(numbers: &[u16], bit_count: u8) -> (u16, u16) {
 /// - To find *CO2 scrubber rating*, determine the least common value in the current
 ///   bit position, and keep only numbers with that bit in that position.
 ///   If 0 and 1 are equally common, keep values with a 0.
-fn compute_ratings(numbers: &[u16], bit_count: u8) -> (u16, u16) {
-    fn bit_matches(bit: u16, n: u16, bit_shift: u8) -> bool {
-        n & (1 << bit_shift) == bit << bit_shift
-    }
+///
+/// (Where a bit that none of the numbers have isn't the least common one, so that
+/// there's always a number left.)
+fn compute_ratings(report: &BitMatrix, tie_break: TieBreak) -> (Rating, Rating) {
+    let rating = |least_common: bool| {
+        let mut rows = report.all_rows();
+        let mut trace = vec![];
 
-    // Note that, when count_0s == count_1s, we say the most common bit is 1 (and the
-    // least common bit is 0) so that we can correctly partition into the two ratings.
-    fn common_bits(numbers: &[u16], bit_shift: u8) -> (u16, u16) {
-        let (count_0s, count_1s) = compute_bit_counts(numbers, bit_shift);
+        for col in 0..report.columns {
+            if count(&rows) <= 1 {
+                break;
+            }
 
-        match Ord::cmp(&count_0s, &count_1s) {
-            std::cmp::Ordering::Less => (1, 0),
-            std::cmp::Ordering::Equal => (1, 0),
-            std::cmp::Ordering::Greater => (0, 1),
+            let (most_common_bit, least_common_bit) = report.common_bits(&rows, col, tie_break);
+            let bit = if least_common {
+                least_common_bit
+            } else {
+                most_common_bit
+            };
+            let kept = report.filter(&rows, col, bit);
+            if count(&kept) > 0 {
+                rows = kept;
+                trace.push((col, bit, count(&rows)));
+            }
         }
-    }
 
-    let bit_shift = bit_count - 1;
-    let (most_common_bit, _) = common_bits(numbers, bit_shift);
-    let (mut oxygen_generators, mut co2_scrubbers) = numbers
-        .iter()
-        .partition::<Vec<u16>, _>(|&n| bit_matches(most_common_bit, *n, bit_shift));
-
-    // @Speed: we could compute `oxygen_generators` and `co2_scrubbers` in parallel.
-    for bit_shift in (0..bit_shift).rev() {
-        if oxygen_generators.len() > 1 {
-            let (most_common_bit, _) = common_bits(&oxygen_generators, bit_shift);
-            oxygen_generators.retain(|n| bit_matches(most_common_bit, *n, bit_shift));
-        }
-        if co2_scrubbers.len() > 1 {
-            let (_, least_common_bit) = common_bits(&co2_scrubbers, bit_shift);
-            co2_scrubbers.retain(|n| bit_matches(least_common_bit, *n, bit_shift));
+        Rating {
+            row: (0..report.rows)
+                .find(|&row| rows[row / 64] >> (row % 64) & 1 == 1)
+                .unwrap(),
+            trace,
         }
-        if oxygen_generators.len() == 1 && co2_scrubbers.len() == 1 {
-            break;
+    };
+
+    (rating(false), rating(true))
+}
+
+impl Day03 {
+    fn tie_break(&self) -> TieBreak {
+        TieBreak {
+            most_common: self.tie_most != Some(0),
+            least_common: self.tie_least.is_some_and(|bit| bit != 0),
         }
     }
+}
+
+impl Solver for Day03 {
+    type Input = BitMatrix;
+    type Output1 = BigUint;
+    type Output2 = BigUint;
+
+    /// Use the binary numbers in your diagnostic report to calculate the gamma rate
+    /// and epsilon rate, then multiply them together. What is the power consumption
+    /// of the submarine? (Be sure to represent your answer in decimal, not binary.)
+    fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
+        let (gamma_rate, epsilon_rate) = compute_rates(input, self.tie_break());
+
+        &gamma_rate * &epsilon_rate
+    }
 
-    let oxygen_generator_rating = oxygen_generators[0];
-    let co2_scrubber_rating = co2_scrubbers[0];
+    /// Use the binary numbers in your diagnostic report to calculate the oxygen generator
+    /// rating and CO2 scrubber rating, then multiply them together.
+    /// What is the life support rating of the submarine?
+    fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
+        let (oxygen_generator_rating, co2_scrubber_rating) =
+            compute_ratings(input, self.tie_break());
+        let value =
+            |rating: Rating| from_bits((0..input.columns).map(|col| input.bit(rating.row, col)));
 
-    (oxygen_generator_rating, co2_scrubber_rating)
+        &value(oxygen_generator_rating) * &value(co2_scrubber_rating)
+    }
+
+    fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
+        let lines = BufReader::new(r).lines().flatten().collect::<Vec<String>>();
+
+        BitMatrix::from_lines(&lines)
+    }
+
+    fn report(&self, input: &Self::Input) {
+        if !self.trace {
+            return;
+        }
+
+        let (oxygen_generator_rating, co2_scrubber_rating) =
+            compute_ratings(input, self.tie_break());
+
+        for (name, rating) in [
+            ("Oxygen generator rating", oxygen_generator_rating),
+            ("CO2 scrubber rating", co2_scrubber_rating),
+        ] {
+            println!("{}: {}", name, input.row(rating.row));
+            for (col, bit, remaining) in rating.trace {
+                println!(
+                    "  bit {} is {}: {} numbers left",
+                    col + 1,
+                    bit as u8,
+                    remaining
+                );
+            }
+        }
+    }
 }
@@ -1,36 +1,63 @@
 //! --- Day 5: Hydrothermal Venture ---
 
-use crate::solver::Solver;
-use core::iter::Iterator;
-use std::{
-    collections::HashMap,
-    io::{self, BufRead, BufReader},
+use crate::{
+    segment::{count_overlaps, count_overlaps_rasterized, Point, Segment},
+    solver::Solver,
 };
+use std::io::{self, BufRead, BufReader};
 
 /// https://adventofcode.com/2021/day/5
-pub struct Day05;
+pub struct Day05 {
+    /// Multiply all coordinates by this before counting overlaps (again).
+    pub scale: Option<i64>,
+    /// Also count overlaps by rasterizing every line, to cross-check the counts.
+    pub cross_check: bool,
+}
+
+fn print_overlaps(segments: &[Segment], cross_check: bool) {
+    let orthogonal = segments
+        .iter()
+        .filter(|segment| segment.is_horizontal() || segment.is_vertical())
+        .copied()
+        .collect::<Vec<_>>();
+
+    for (lines, segments) in [
+        ("horizontal and vertical", &orthogonal[..]),
+        ("all", segments),
+    ] {
+        let overlaps = count_overlaps(segments);
+        if cross_check {
+            let rasterized = count_overlaps_rasterized(segments);
+            let check = if overlaps == rasterized {
+                "ok"
+            } else {
+                "MISMATCH"
+            };
+            println!(
+                "Overlaps of {} lines: {} (rasterized: {}, {})",
+                lines, overlaps, rasterized, check
+            );
+        } else {
+            println!("Overlaps of {} lines: {}", lines, overlaps);
+        }
+    }
+}
 
 impl Solver for Day05 {
-    type Input = Vec<((i32, i32), (i32, i32))>;
+    type Input = Vec<Segment>;
     type Output1 = usize;
     type Output2 = usize;
 
     /// Consider only horizontal and vertical lines.
     /// At how many points do at least two lines overlap?
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let mut points = HashMap::<(i32, i32), i32>::new();
+        let segments = input
+            .iter()
+            .filter(|segment| segment.is_horizontal() || segment.is_vertical())
+            .copied()
+            .collect::<Vec<_>>();
 
-        for &((x1, y1), (x2, y2)) in input {
-            if x1 == x2 {
-                if y1 < y2 { y1..=y2 } else { y2..=y1 }
-                    .for_each(|y| *points.entry((x1, y)).or_insert(0) += 1)
-            } else if y1 == y2 {
-                if x1 < x2 { x1..=x2 } else { x2..=x1 }
-                    .for_each(|x| *points.entry((x, y1)).or_insert(0) += 1)
-            }
-        }
-
-        points.values().filter(|&count| *count > 1).count()
+        count_overlaps(&segments)
     }
 
     /// Unfortunately, considering only horizontal and vertical lines doesn't give you the
@@ -38,30 +65,7 @@ impl Solver for Day05 {
     /// will only ever be horizontal, vertical, or a diagonal line at exactly 45 degrees.
     /// At how many points do at least two lines overlap?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let mut points = HashMap::<(i32, i32), i32>::new();
-
-        for &((x1, y1), (x2, y2)) in input {
-            if x1 == x2 {
-                if y1 < y2 { y1..=y2 } else { y2..=y1 }
-                    .for_each(|y| *points.entry((x1, y)).or_insert(0) += 1)
-            } else if y1 == y2 {
-                if x1 < x2 { x1..=x2 } else { x2..=x1 }
-                    .for_each(|x| *points.entry((x, y1)).or_insert(0) += 1)
-            } else {
-                match (x1 < x2, y1 < y2) {
-                    (true, true) => Iterator::zip(x1..=x2, y1..=y2)
-                        .for_each(|xy| *points.entry(xy).or_insert(0) += 1),
-                    (true, false) => Iterator::zip(x1..=x2, (y2..=y1).rev())
-                        .for_each(|xy| *points.entry(xy).or_insert(0) += 1),
-                    (false, true) => Iterator::zip((x2..=x1).rev(), y1..=y2)
-                        .for_each(|xy| *points.entry(xy).or_insert(0) += 1),
-                    (false, false) => Iterator::zip((x2..=x1).rev(), (y2..=y1).rev())
-                        .for_each(|xy| *points.entry(xy).or_insert(0) += 1),
-                }
-            }
-        }
-
-        points.values().filter(|&count| *count > 1).count()
+        count_overlaps(input)
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
@@ -72,12 +76,40 @@ impl Solver for Day05 {
                 match line
                     .split(" -> ")
                     .flat_map(|coord| coord.split(',').flat_map(|c| c.parse()))
-                    .collect::<Vec<i32>>()[..]
+                    .collect::<Vec<i64>>()[..]
                 {
-                    [x1, y1, x2, y2] => ((x1, y1), (x2, y2)),
+                    [x1, y1, x2, y2] => Segment {
+                        start: Point { x: x1, y: y1 },
+                        end: Point { x: x2, y: y2 },
+                    },
                     _ => unreachable!(),
                 }
             })
-            .collect::<Vec<((i32, i32), (i32, i32))>>()
+            .collect()
+    }
+
+    fn report(&self, input: &Self::Input) {
+        if self.cross_check {
+            print_overlaps(input, true);
+        }
+
+        if let Some(scale) = self.scale {
+            let scaled = input
+                .iter()
+                .map(|segment| Segment {
+                    start: Point {
+                        x: segment.start.x * scale,
+                        y: segment.start.y * scale,
+                    },
+                    end: Point {
+                        x: segment.end.x * scale,
+                        y: segment.end.y * scale,
+                    },
+                })
+                .collect::<Vec<_>>();
+
+            println!("Scaled by {}:", scale);
+            print_overlaps(&scaled, self.cross_check);
+        }
     }
 }
@@ -1,33 +1,95 @@
 //! --- Day 6: Lanternfish ---
 
-use crate::solver::Solver;
+use crate::{
+    bigint::BigUint,
+    matrix::{Matrix, Modular, Ring},
+    solver::Solver,
+};
+use std::fmt::Display;
 use std::io::{self, BufRead, BufReader};
 
 /// https://adventofcode.com/2021/day/6
-pub struct Day06;
+pub struct Day06 {
+    /// Also print how many lanternfish there would be after this many days.
+    pub days: Option<u64>,
+    /// Also print how many lanternfish there would be after this many days,
+    /// without simulating each one of them (so with `modulo`, it can be huge,
+    /// like 10^18).
+    pub fast_forward: Option<u64>,
+    /// Count the fast-forwarded lanternfish modulo this (instead of exactly, which
+    /// is only possible for up to `MAX_EXACT_DAYS` days).
+    pub modulo: Option<u64>,
+}
+
+/// Most days to fast-forward with exact counts, whose number of digits grows linearly
+/// with the days (so that each matrix multiplication gets slower and slower).
+const MAX_EXACT_DAYS: u64 = 100_000;
 
 /// You can model each fish as a single number that represents the number of days until
 /// it creates a new lanternfish. Furthermore, you reason, a new lanternfish would surely
 /// need slightly longer before it's capable of producing more lanternfish: two more days
 /// for its first cycle.
-fn count_after(days: u64, ages: &[u8]) -> u64 {
-    let mut count = [0; 9];
+fn count_after(days: u64, ages: &[u8]) -> BigUint {
+    let mut count = [0_u64; 9];
     for &age in ages {
         count[age as usize] += 1;
     }
+    let mut count = count.map(BigUint::from);
     for _ in 0..days {
         count.rotate_left(1);
         // Account for the "parents" which go back to 6
         // (while the "new born" lanterfish start at 8).
-        count[6] += count[8];
+        let new_born = count[8].clone();
+        count[6] += &new_born;
     }
     count.iter().sum()
 }
 
+/// Number of lanternfish with each timer value (from 0 up to 8).
+struct School<T>(Vec<T>);
+
+impl<T: Ring> School<T> {
+    /// Counts the lanternfish by their `ages`, using the same kind of integer as `unit`.
+    fn new(ages: &[u8], unit: &T) -> Self {
+        School(
+            (0..9)
+                .map(|timer| unit.of(ages.iter().filter(|&&age| age == timer).count() as u64))
+                .collect(),
+        )
+    }
+
+    /// Advances `days` days at once, since each of them is the same linear map: every
+    /// timer decreases by one, except at 0, which goes back to 6 and creates a new one at 8.
+    fn fast_forward(&mut self, days: u64) {
+        let unit = &self.0[0];
+        let day = Matrix::from_fn(9, |row, col| {
+            unit.of(match (row, col) {
+                (6, 0) | (8, 0) => 1,
+                _ if col == row + 1 => 1,
+                _ => 0,
+            })
+        });
+
+        self.0 = day.pow(days).apply(&self.0);
+    }
+
+    fn count(&self) -> T {
+        self.0
+            .iter()
+            .fold(self.0[0].of(0), |sum, count| sum.plus(count))
+    }
+}
+
+fn print_fast_forward<T: Ring + Display>(days: u64, ages: &[u8], unit: T) {
+    let mut school = School::new(ages, &unit);
+    school.fast_forward(days);
+    println!("After {} days: {}", days, school.count());
+}
+
 impl Solver for Day06 {
     type Input = Vec<u8>;
-    type Output1 = u64;
-    type Output2 = u64;
+    type Output1 = BigUint;
+    type Output2 = BigUint;
 
     /// How many lanternfish would there be after 80 days?
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
@@ -50,4 +112,21 @@ impl Solver for Day06 {
             })
             .collect()
     }
+
+    fn report(&self, input: &Self::Input) {
+        if let Some(days) = self.days {
+            println!("After {} days: {}", days, count_after(days, input));
+        }
+
+        if let Some(days) = self.fast_forward {
+            match self.modulo {
+                Some(modulus) => print_fast_forward(days, input, Modular::new(1, modulus)),
+                None if days > MAX_EXACT_DAYS => println!(
+                    "Can't count the lanternfish exactly after more than {} days, use --modulo",
+                    MAX_EXACT_DAYS
+                ),
+                None => print_fast_forward(days, input, BigUint::from(1)),
+            }
+        }
+    }
 }
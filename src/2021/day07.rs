@@ -2,37 +2,149 @@
 
 use crate::solver::Solver;
 use std::{
-    collections::HashMap,
     io::{self, BufRead, BufReader},
+    str::FromStr,
 };
 
 /// https://adventofcode.com/2021/day/7
-pub struct Day07;
+pub struct Day07 {
+    /// Print the best alignment using this cost function (e.g. "linear", "triangular",
+    /// "pow:3" or "poly:0,2,1", with the polynomial's coefficients from the lowest degree).
+    pub cost: Option<Cost>,
+}
 
-impl Solver for Day07 {
-    type Input = Vec<i32>;
-    type Output1 = i32;
-    type Output2 = i32;
+/// Fuel spent by a crab to move some number of steps, which is convex (so that the total
+/// fuel spent by all crabs is also convex over the position they align to).
+pub enum Cost {
+    /// Each step costs 1 fuel.
+    Linear,
+    /// Each step costs 1 more fuel than the last.
+    Triangular,
+    /// Moving n steps costs `c[0] + c[1] * n + c[2] * n^2 + ...` (for non-negative `c[i]`).
+    Polynomial(Vec<i64>),
+}
 
-    /// Determine the horizontal position that the crabs can align to using the
-    /// least fuel possible. How much fuel must they spend to align to that position?
-    fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let mut crabs_per_pos = HashMap::<i32, i32>::new();
-        for &pos in input {
-            *crabs_per_pos.entry(pos).or_insert(0) += 1;
+impl Cost {
+    /// Returns the fuel spent to move `steps` steps, or `None` if it overflows.
+    fn of(&self, steps: i64) -> Option<i128> {
+        let steps = steps as i128;
+        match self {
+            Cost::Linear => Some(steps),
+            // If a crab walks n positions to align, then:
+            // 1 + 2 + ... + n = n * (n + 1) / 2.
+            Cost::Triangular => Some((steps * steps + steps) / 2),
+            Cost::Polynomial(coefficients) => coefficients
+                .iter()
+                .rev()
+                .try_fold(0_i128, |acc, &coefficient| {
+                    acc.checked_mul(steps)?.checked_add(coefficient as i128)
+                }),
         }
+    }
+}
+
+impl FromStr for Cost {
+    type Err = String;
 
-        let costs = crabs_per_pos
-            .keys()
-            .map(|&align_pos| {
-                crabs_per_pos
-                    .iter()
-                    .map(|(&other_pos, &count)| count * (align_pos - other_pos).abs())
-                    .sum()
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_coefficient = |c: &str| match c.parse::<i64>() {
+            Ok(c) if c >= 0 => Ok(c),
+            _ => Err(format!("invalid (non-negative) coefficient: {}", c)),
+        };
+
+        match s.split_once(':') {
+            None if s == "linear" => Ok(Cost::Linear),
+            None if s == "triangular" => Ok(Cost::Triangular),
+            Some(("pow", exp)) => {
+                let exp = exp.parse::<usize>().map_err(|e| e.to_string())?;
+                let mut coefficients = vec![0; exp + 1];
+                coefficients[exp] = 1;
+                Ok(Cost::Polynomial(coefficients))
+            }
+            Some(("poly", coefficients)) => coefficients
+                .split(',')
+                .map(parse_coefficient)
+                .collect::<Result<_, _>>()
+                .map(Cost::Polynomial),
+            _ => Err(format!("unknown cost function: {}", s)),
+        }
+    }
+}
+
+/// Position that the crabs align to, and how much fuel they spend.
+pub struct Alignment {
+    position: i64,
+    fuel: i128,
+}
+
+/// Returns the total fuel spent by all `crabs` to align to `position`, or `None` if it
+/// overflows (as it can with user-supplied costs).
+fn fuel(crabs: &[i64], position: i64, cost: &Cost) -> Option<i128> {
+    crabs.iter().try_fold(0_i128, |total, &crab| {
+        total.checked_add(cost.of((crab - position).abs())?)
+    })
+}
+
+/// Returns the alignment (within `range`) that spends the least fuel.
+fn cheapest(crabs: &[i64], range: impl Iterator<Item = i64>, cost: &Cost) -> Option<Alignment> {
+    range
+        .map(|position| {
+            Some(Alignment {
+                position,
+                fuel: fuel(crabs, position, cost)?,
             })
-            .collect::<Vec<i32>>();
+        })
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min_by_key(|alignment| alignment.fuel)
+}
+
+/// Returns the alignment that spends the least fuel, given the (sorted) `crabs` positions,
+/// or `None` if the fuel overflows.
+fn align(crabs: &[i64], cost: &Cost) -> Option<Alignment> {
+    match cost {
+        // The sum of distances is minimized by the median.
+        Cost::Linear => {
+            let median = crabs[crabs.len() / 2];
+            cheapest(crabs, median..=median, cost)
+        }
+
+        // The sum of (n^2 + n) / 2 distances is minimized within 1/2 of the mean.
+        Cost::Triangular => {
+            let mean = crabs.iter().sum::<i64>().div_euclid(crabs.len() as i64);
+            cheapest(crabs, mean - 1..=mean + 1, cost)
+        }
 
-        *costs.iter().min().unwrap()
+        // Since the total fuel is convex, we can ternary search for its minimum.
+        Cost::Polynomial(_) => {
+            let (mut lo, mut hi) = (crabs[0], crabs[crabs.len() - 1]);
+            while hi - lo > 2 {
+                let mid_lo = lo + (hi - lo) / 3;
+                let mid_hi = hi - (hi - lo) / 3;
+                let (fuel_lo, fuel_hi) = (fuel(crabs, mid_lo, cost)?, fuel(crabs, mid_hi, cost)?);
+                if fuel_lo < fuel_hi {
+                    hi = mid_hi - 1;
+                } else if fuel_lo > fuel_hi {
+                    lo = mid_lo + 1;
+                } else {
+                    lo = mid_lo;
+                    hi = mid_hi;
+                }
+            }
+            cheapest(crabs, lo..=hi, cost)
+        }
+    }
+}
+
+impl Solver for Day07 {
+    type Input = Vec<i64>;
+    type Output1 = i128;
+    type Output2 = i128;
+
+    /// Determine the horizontal position that the crabs can align to using the
+    /// least fuel possible. How much fuel must they spend to align to that position?
+    fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
+        align(input, &Cost::Linear).unwrap().fuel
     }
 
     /// As it turns out, crab submarine engines don't burn fuel at a constant rate.
@@ -41,51 +153,33 @@ impl Solver for Day07 {
     /// possible so they can make you an escape route!
     /// How much fuel must they spend to align to that position?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let mut crabs_per_pos = HashMap::<i32, i32>::new();
-        for &pos in input {
-            *crabs_per_pos.entry(pos).or_insert(0) += 1;
-        }
-
-        // @Note: not sure if this wasn't needed in the first case by luck, or if the
-        // instructions weren't clear enough... but it worked without it ¯\_(ツ)_/¯.
-        let (min, max) = input[1..]
-            .iter()
-            .fold((input[0], input[0]), |(min, max), &pos| {
-                if pos < min {
-                    (pos, max)
-                } else if pos > max {
-                    (min, pos)
-                } else {
-                    (min, max)
-                }
-            });
-
-        let costs = (min..=max)
-            .map(|align_pos| {
-                crabs_per_pos
-                    .iter()
-                    .map(|(&other_pos, &count)| {
-                        let steps = (align_pos - other_pos).abs();
-                        // If a crab walks n positions to align, then:
-                        // 1 + 2 + ... + n = n * (n + 1) / 2.
-                        count * (steps * steps + steps) / 2
-                    })
-                    .sum()
-            })
-            .collect::<Vec<i32>>();
-
-        *costs.iter().min().unwrap()
+        align(input, &Cost::Triangular).unwrap().fuel
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
-        BufReader::new(r)
+        let mut crabs = BufReader::new(r)
             .lines()
             .flatten()
             .flat_map(|line| {
                 line.split(',')
-                    .map(|age| age.parse().unwrap())
-                    .collect::<Vec<i32>>()
+                    .map(|pos| pos.parse().unwrap())
+                    .collect::<Vec<i64>>()
             })
-            .collect()
+            .collect::<Vec<_>>();
+
+        crabs.sort_unstable();
+
+        crabs
+    }
+
+    fn report(&self, input: &Self::Input) {
+        if let Some(cost) = &self.cost {
+            match align(input, cost) {
+                Some(Alignment { position, fuel }) => {
+                    println!("Align to position {} using {} fuel", position, fuel)
+                }
+                None => println!("The fuel spent overflows with this cost function"),
+            }
+        }
     }
 }
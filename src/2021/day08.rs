@@ -1,12 +1,194 @@
 //! --- Day 8: Seven Segment Search ---
 
-use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use crate::{assignment::all_assignments, solver::Solver};
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader},
+};
 
 /// https://adventofcode.com/2021/day/8
-pub struct Day08;
+pub struct Day08 {
+    /// Glyph set used to decode each entry when reporting: "decimal" (default) or "hex".
+    pub glyphs: Option<String>,
+    /// Print the decoded output of each entry, rendered as seven-segment displays.
+    pub render: bool,
+}
+
+const SEGMENT_COUNT: usize = 7;
+
+/// Segments which are turned on, as bits `abcdefg` (i.e. the highest bit is `a`):
+///
+/// ```text
+///  aaaa
+/// b    c
+/// b    c
+///  dddd
+/// e    f
+/// e    f
+///  gggg
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SevenSegmentDisplay(u8);
+
+const NUMBER: [SevenSegmentDisplay; 10] = [
+    //                    abcdefg
+    SevenSegmentDisplay(0b1110111), // 0: abcefg
+    SevenSegmentDisplay(0b0010010), // 1: cf        (only digit that uses 2 segments)
+    SevenSegmentDisplay(0b1011101), // 2: acdeg
+    SevenSegmentDisplay(0b1011011), // 3: acdfg
+    SevenSegmentDisplay(0b0111010), // 4: bcdf      (only digit that uses 4 segments)
+    SevenSegmentDisplay(0b1101011), // 5: abdfg
+    SevenSegmentDisplay(0b1101111), // 6: abdefg
+    SevenSegmentDisplay(0b1010010), // 7: acf       (only digit that uses 3 segments)
+    SevenSegmentDisplay(0b1111111), // 8: abcdefg   (only digit that uses 7 segments)
+    SevenSegmentDisplay(0b1111011), // 9: abcdfg
+];
+
+const HEX_NUMBER: [SevenSegmentDisplay; 16] = [
+    NUMBER[0],
+    NUMBER[1],
+    NUMBER[2],
+    NUMBER[3],
+    NUMBER[4],
+    NUMBER[5],
+    NUMBER[6],
+    NUMBER[7],
+    NUMBER[8],
+    NUMBER[9],
+    //                    abcdefg
+    SevenSegmentDisplay(0b1111110), // A: abcdef
+    SevenSegmentDisplay(0b0101111), // b: bdefg
+    SevenSegmentDisplay(0b1100101), // C: abeg
+    SevenSegmentDisplay(0b0011111), // d: cdefg
+    SevenSegmentDisplay(0b1101101), // E: abdeg
+    SevenSegmentDisplay(0b1101100), // F: abde
+];
+
+impl SevenSegmentDisplay {
+    /// Returns the display rendered by turning on `wires`, with `wiring` mapping
+    /// each wire to the index of the segment it's connected to (in "abcdefg").
+    fn from_wires(wires: &str, wiring: &[usize]) -> Self {
+        SevenSegmentDisplay(wires.bytes().fold(0, |display, wire| {
+            display | (1 << (SEGMENT_COUNT - 1 - wiring[(wire - b'a') as usize]))
+        }))
+    }
+
+    fn is_on(&self, segment: usize) -> bool {
+        (self.0 >> (SEGMENT_COUNT - 1 - segment)) & 1 == 1
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DecodeError {
+    /// There's no wiring with which every pattern renders a different glyph.
+    Contradictory,
+    /// There's more than one wiring with which every pattern renders a different glyph.
+    Ambiguous(usize),
+    /// The output pattern doesn't render any glyph.
+    UnknownOutput(String),
+}
 
-const ABCDEFG: [char; 7] = ['a', 'b', 'c', 'd', 'e', 'f', 'g'];
+/// Returns the wiring (mapping each wire to the index of the segment it's connected to)
+/// with which each of the `patterns` renders a different one of the `glyphs`.
+fn find_wiring(
+    patterns: &[String],
+    glyphs: &[SevenSegmentDisplay],
+) -> Result<Vec<usize>, DecodeError> {
+    // When every glyph shows up in the patterns, a wire (and the segment it's connected
+    // to) is used by the same number of patterns (and glyphs) of each length, so we use
+    // that to narrow down which segments each wire may be connected to, e.g. for digits,
+    // 'b' is the only one in 6 of them, but 'a' and 'c' are both in 8 (though only 'c'
+    // is in the 1, "cf"). Otherwise, any wire may be connected to any segment.
+    let signature = |lens: &mut dyn Iterator<Item = usize>| {
+        let mut lens = lens.collect::<Vec<_>>();
+        lens.sort_unstable();
+        lens
+    };
+
+    let candidates = (0..SEGMENT_COUNT)
+        .map(|wire| {
+            let chr = (b'a' + wire as u8) as char;
+            let wire_signature = signature(
+                &mut patterns
+                    .iter()
+                    .filter(|pattern| pattern.contains(chr))
+                    .map(|pattern| pattern.len()),
+            );
+            (0..SEGMENT_COUNT)
+                .filter(|&segment| {
+                    let segment_signature = signature(
+                        &mut glyphs
+                            .iter()
+                            .filter(|glyph| glyph.is_on(segment))
+                            .map(|glyph| glyph.0.count_ones() as usize),
+                    );
+                    patterns.len() != glyphs.len() || segment_signature == wire_signature
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    let mut wirings = all_assignments(&candidates, SEGMENT_COUNT)
+        .into_iter()
+        .filter(|wiring| {
+            let mut rendered = patterns
+                .iter()
+                .map(|pattern| SevenSegmentDisplay::from_wires(pattern, wiring))
+                .collect::<Vec<_>>();
+            let renders_glyphs = rendered.iter().all(|display| glyphs.contains(display));
+            rendered.sort_unstable_by_key(|display| display.0);
+            rendered.dedup();
+            renders_glyphs && rendered.len() == patterns.len()
+        })
+        .collect::<Vec<_>>();
+
+    match wirings.len() {
+        0 => Err(DecodeError::Contradictory),
+        1 => Ok(wirings.pop().unwrap()),
+        n => Err(DecodeError::Ambiguous(n)),
+    }
+}
+
+/// Returns the index of the glyph rendered by each of the `output` patterns.
+fn decode_output(
+    patterns: &[String],
+    output: &[String],
+    glyphs: &[SevenSegmentDisplay],
+) -> Result<Vec<usize>, DecodeError> {
+    let wiring = find_wiring(patterns, glyphs)?;
+
+    output
+        .iter()
+        .map(|encoded| {
+            let display = SevenSegmentDisplay::from_wires(encoded, &wiring);
+            glyphs
+                .iter()
+                .position(|&glyph| glyph == display)
+                .ok_or_else(|| DecodeError::UnknownOutput(encoded.clone()))
+        })
+        .collect()
+}
+
+/// Renders the `displays` side by side.
+fn render(displays: &[SevenSegmentDisplay]) -> String {
+    let rendered = displays
+        .iter()
+        .map(|display| display.to_string())
+        .collect::<Vec<_>>();
+    let mut lines = rendered.iter().map(|r| r.lines()).collect::<Vec<_>>();
+
+    let mut render = String::new();
+    while let Some(line) = lines
+        .iter_mut()
+        .map(|lines| lines.next())
+        .collect::<Option<Vec<_>>>()
+    {
+        render.push_str(&line.join("  "));
+        render.push('\n');
+    }
+
+    render
+}
 
 impl Solver for Day08 {
     type Input = Vec<(Vec<String>, Vec<String>)>;
@@ -32,83 +214,23 @@ impl Solver for Day08 {
 
     /// For each entry, determine all of the wire/segment connections and decode the
     /// four-digit output values. What do you get if you add up all of the output values?
+    ///
+    /// (Entries that can't be decoded are skipped, with a message saying why.)
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        fn decode_output(patterns: &[String], output: &[String]) -> usize {
-            // In the ten unique signal patterns, the frequency of each letter in "abcdefg"
-            // is [8, 6, 8, 7, 4, 9, 7]. Since 6, 4 and 9 are unique, we can find out "bef":
-            let freq = patterns.iter().fold([0; 7], |mut freq, pattern| {
-                pattern.chars().for_each(|chr| {
-                    freq[(chr as u8 - b'a') as usize] += 1;
-                });
-                freq
-            });
-
-            let b = ABCDEFG[freq.iter().position(|&f| f == 6).unwrap()] as u8;
-            let e = ABCDEFG[freq.iter().position(|&f| f == 4).unwrap()] as u8;
-            let f = ABCDEFG[freq.iter().position(|&f| f == 9).unwrap()] as u8;
-
-            // Digits 1, 4, 7, and 8 each use a unique number of segments:
-            let pattern_1 = patterns.iter().find(|p| p.len() == 2).unwrap().as_bytes(); // cf
-            let pattern_4 = patterns.iter().find(|p| p.len() == 4).unwrap().as_bytes(); // bcdf
-            let pattern_7 = patterns.iter().find(|p| p.len() == 3).unwrap().as_bytes(); // acf
-            let pattern_8 = patterns.iter().find(|p| p.len() == 7).unwrap().as_bytes(); // abcdefg
-
-            #[rustfmt::skip]
-            let (a, c, d, g) = {
-                let a = *pattern_7.iter().find(|&chr| !pattern_1.contains(chr)).unwrap();
-                let c = *pattern_7.iter().find(|&chr| ![a, f].contains(chr)).unwrap();
-                let d = *pattern_4.iter().find(|&chr| ![b, c, f].contains(chr)).unwrap();
-                let g = *pattern_8.iter().find(|&chr| ![a, b, c, d, e, f].contains(chr)).unwrap();
-                (a, c, d, g)
-            };
-
-            let decode = {
-                let mut decode = [' '; 7];
-                decode[(a - b'a') as usize] = 'a';
-                decode[(b - b'a') as usize] = 'b';
-                decode[(c - b'a') as usize] = 'c';
-                decode[(d - b'a') as usize] = 'd';
-                decode[(e - b'a') as usize] = 'e';
-                decode[(f - b'a') as usize] = 'f';
-                decode[(g - b'a') as usize] = 'g';
-                decode
-            };
-
-            output
-                .iter()
-                .enumerate()
-                .map(|(i, encoded)| {
-                    let decoded = {
-                        let mut decoded = encoded
-                            .chars()
-                            .map(|chr| decode[(chr as u8 - b'a') as usize])
-                            .collect::<Vec<_>>();
-                        decoded.sort_unstable();
-                        decoded.into_iter().collect::<String>()
-                    };
-
-                    let value = match &decoded[..] {
-                        "abcefg" => 0,
-                        "cf" => 1,
-                        "acdeg" => 2,
-                        "acdfg" => 3,
-                        "bcdf" => 4,
-                        "abdfg" => 5,
-                        "abdefg" => 6,
-                        "acf" => 7,
-                        "abcdefg" => 8,
-                        "abcdfg" => 9,
-                        _ => unreachable!(),
-                    };
-
-                    value * 10_usize.pow(3 - i as u32)
-                })
-                .sum()
-        }
-
         input
             .iter()
-            .map(|(patterns, output)| decode_output(patterns, output))
+            .enumerate()
+            .filter_map(|(i, (patterns, output))| {
+                let digits = decode_output(patterns, output, &NUMBER)
+                    .map_err(|err| eprintln!("Skipping entry {}: {:?}", i + 1, err))
+                    .ok()?;
+
+                Some(
+                    digits
+                        .into_iter()
+                        .fold(0, |value, digit| value * 10 + digit),
+                )
+            })
             .sum()
     }
 
@@ -120,50 +242,40 @@ impl Solver for Day08 {
                 let (patterns, output) = line.split_once(" | ").unwrap();
 
                 let patterns = patterns.split(' ').map(String::from).collect::<Vec<_>>();
-                assert_eq!(patterns.len(), 10); // all ten unique signal patters
-
                 let output = output.split(' ').map(String::from).collect::<Vec<_>>();
-                assert_eq!(output.len(), 4); // four digit output value
 
                 (patterns, output)
             })
             .collect::<Vec<(Vec<String>, Vec<String>)>>()
     }
-}
 
-/*
-struct SevenSegmentDisplay(u8);
+    fn report(&self, input: &Self::Input) {
+        if self.glyphs.is_none() && !self.render {
+            return;
+        }
 
-const NUMBER: [SevenSegmentDisplay; 10] = [
-    //                    abcdefg
-    SevenSegmentDisplay(0b1110111), // 0: abcefg
-    SevenSegmentDisplay(0b0010010), // 1: cf        (only digit that uses 2 segments)
-    SevenSegmentDisplay(0b1011101), // 2: acdeg
-    SevenSegmentDisplay(0b1011011), // 3: acdfg
-    SevenSegmentDisplay(0b0111010), // 4: bcdf      (only digit that uses 4 segments)
-    SevenSegmentDisplay(0b1101011), // 5: abdfg
-    SevenSegmentDisplay(0b1101111), // 6: abdefg
-    SevenSegmentDisplay(0b1010010), // 7: acf       (only digit that uses 3 segments)
-    SevenSegmentDisplay(0b1111111), // 8: abcdefg   (only digit that uses 7 segments)
-    SevenSegmentDisplay(0b1111011), // 9: abcdfg
-];
+        let glyphs: &[SevenSegmentDisplay] = match self.glyphs.as_deref() {
+            None | Some("decimal") => &NUMBER,
+            Some("hex") => &HEX_NUMBER,
+            Some(glyphs) => panic!("unknown glyph set: {}", glyphs),
+        };
 
-impl SevenSegmentDisplay {
-    fn new(a: bool, b: bool, c: bool, d: bool, e: bool, f: bool, g: bool) -> Self {
-        SevenSegmentDisplay(
-            ((a as u8) << 6)
-                & ((b as u8) << 5)
-                & ((c as u8) << 4)
-                & ((d as u8) << 3)
-                & ((e as u8) << 2)
-                & ((f as u8) << 1)
-                & (g as u8),
-        )
+        for (i, (patterns, output)) in input.iter().enumerate() {
+            match decode_output(patterns, output, glyphs) {
+                Ok(output) => {
+                    let digits = output.iter().map(|&digit| format!("{:X}", digit));
+                    println!("Entry {}: {}", i + 1, digits.collect::<String>());
+                    if self.render {
+                        let displays = output.iter().map(|&digit| glyphs[digit]);
+                        println!("{}", render(&displays.collect::<Vec<_>>()));
+                    }
+                }
+                Err(err) => println!("Entry {}: {:?}", i + 1, err),
+            }
+        }
     }
 }
 
-use std::fmt::Display;
-
 impl Display for SevenSegmentDisplay {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let is_on = |bit: u8| (self.0 >> bit) & 1 == 1;
@@ -185,4 +297,3 @@ impl Display for SevenSegmentDisplay {
         Ok(())
     }
 }
-*/
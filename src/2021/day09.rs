@@ -1,35 +1,183 @@
 //! --- Day 9: Smoke Basin ---
 
 use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use std::{
+    fs,
+    io::{self, BufRead, BufReader},
+};
 
 /// https://adventofcode.com/2021/day/9
-pub struct Day09;
+pub struct Day09 {
+    /// Locations of this height (or higher) aren't in any basin (defaults to 9).
+    pub ridge: Option<u8>,
+    /// Consider diagonal locations adjacent too.
+    pub diagonal: bool,
+    /// Print the heightmap in the terminal, coloured by basin.
+    pub render: bool,
+    /// Write the heightmap to this PPM image, coloured by basin.
+    pub ppm: Option<String>,
+}
+
+const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const ALL_AROUND: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+pub struct Heightmap {
+    heights: Vec<u8>,
+    width: usize,
+}
 
-fn adjacent_indices(index: usize, width: usize, length: usize) -> impl Iterator<Item = usize> {
-    let x = index % width;
-    let y = index / width;
+/// Segmentation of a heightmap into basins, which are identified by their index.
+struct Basins {
+    /// Basin of each location, if it's not on a ridge.
+    labels: Vec<Option<usize>>,
+    /// Lowest location of each basin.
+    low_points: Vec<usize>,
+    /// Number of locations in each basin.
+    sizes: Vec<usize>,
+}
 
-    let xs = match x {
-        0 => vec![x + 1],
-        _ if x < width - 1 => vec![x - 1, x + 1],
-        _ => vec![x - 1],
+impl Heightmap {
+    fn len(&self) -> usize {
+        self.heights.len() / self.width
     }
-    .into_iter();
-    let ys = match y {
-        0 => vec![y + 1],
-        _ if y < length - 1 => vec![y - 1, y + 1],
-        _ => vec![y - 1],
+
+    fn adjacent_indices(&self, index: usize, diagonal: bool) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = ((index % self.width) as isize, (index / self.width) as isize);
+        let offsets: &[(isize, isize)] = if diagonal { &ALL_AROUND } else { &ORTHOGONAL };
+
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let (x, y) = (x + dx, y + dy);
+            let is_inside =
+                (0..self.width as isize).contains(&x) && (0..self.len() as isize).contains(&y);
+            is_inside.then(|| x as usize + y as usize * self.width)
+        })
+    }
+
+    /// Returns the locations that are lower than any of their adjacent locations.
+    fn low_points(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.heights.len()).filter(move |&index| {
+            self.adjacent_indices(index, false)
+                .all(|adjacent| self.heights[index] < self.heights[adjacent])
+        })
     }
-    .into_iter();
 
-    xs.map(move |xx| (xx, y))
-        .chain(ys.map(move |yy| (x, yy)))
-        .map(move |(xx, yy)| xx + yy * width)
+    /// Splits the locations lower than `ridge` into basins of adjacent locations, where
+    /// each basin is filled from its lowest location (so that every location is in one,
+    /// even if there's no single low point).
+    fn basins(&self, ridge: u8, diagonal: bool) -> Basins {
+        let mut basins = Basins {
+            labels: vec![None; self.heights.len()],
+            low_points: vec![],
+            sizes: vec![],
+        };
+
+        let mut by_height = (0..self.heights.len())
+            .filter(|&index| self.heights[index] < ridge)
+            .collect::<Vec<_>>();
+        by_height.sort_by_key(|&index| self.heights[index]);
+
+        for low_point in by_height {
+            if basins.labels[low_point].is_some() {
+                continue;
+            }
+
+            let basin = basins.low_points.len();
+            let mut size = 0;
+            let mut dfs = vec![low_point];
+            basins.labels[low_point] = Some(basin);
+            while let Some(index) = dfs.pop() {
+                size += 1;
+                for adjacent in self.adjacent_indices(index, diagonal) {
+                    if basins.labels[adjacent].is_none() && self.heights[adjacent] < ridge {
+                        basins.labels[adjacent] = Some(basin);
+                        dfs.push(adjacent);
+                    }
+                }
+            }
+
+            basins.low_points.push(low_point);
+            basins.sizes.push(size);
+        }
+
+        basins
+    }
+}
+
+/// Returns the colour of `basin`, with hues spread around the colour wheel by the
+/// golden angle, so that basins with close ids look different.
+fn basin_colour(basin: Option<usize>) -> [u8; 3] {
+    let basin = match basin {
+        Some(basin) => basin,
+        None => return [0, 0, 0],
+    };
+
+    let hue = (basin as f64 * 0.618_033_988_75).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u8 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let channel = |c: f64| (64.0 + 160.0 * c) as u8;
+
+    [channel(r), channel(g), channel(b)]
+}
+
+/// Returns the heightmap with each location on the background colour of its basin,
+/// and low points underlined.
+fn render(heightmap: &Heightmap, basins: &Basins) -> String {
+    let mut rendered = String::new();
+
+    for (index, height) in heightmap.heights.iter().enumerate() {
+        let [r, g, b] = basin_colour(basins.labels[index]);
+        let underline = if basins.low_points.contains(&index) {
+            ";4"
+        } else {
+            ""
+        };
+        rendered.push_str(&format!(
+            "\x1b[48;2;{};{};{}{}m{}",
+            r, g, b, underline, height
+        ));
+
+        if (index + 1) % heightmap.width == 0 {
+            rendered.push_str("\x1b[0m\n");
+        }
+    }
+
+    rendered
+}
+
+/// Returns a (binary) PPM image of the heightmap, with a pixel per location in the
+/// colour of its basin, darker for higher locations.
+fn ppm(heightmap: &Heightmap, basins: &Basins) -> Vec<u8> {
+    let max_height = heightmap.heights.iter().max().copied().unwrap_or(0).max(1) as u32;
+    let mut image = format!("P6\n{} {}\n255\n", heightmap.width, heightmap.len()).into_bytes();
+
+    for (index, &height) in heightmap.heights.iter().enumerate() {
+        let colour = basin_colour(basins.labels[index]);
+        image.extend(
+            colour.map(|c| (c as u32 * (2 * max_height - height as u32) / (2 * max_height)) as u8),
+        );
+    }
+
+    image
 }
 
 impl Solver for Day09 {
-    type Input = (Vec<u8>, usize);
+    type Input = Heightmap;
     type Output1 = usize;
     type Output2 = usize;
 
@@ -38,15 +186,10 @@ impl Solver for Day09 {
     ///
     /// What is the sum of the risk levels of all low points on your heightmap?
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let (heightmap, width) = input;
-        let length = heightmap.len() / width;
-        let basins = heightmap.iter().enumerate().filter(|(i, height)| {
-            adjacent_indices(*i, *width, length).all(|ii| **height < heightmap[ii])
-        });
-
-        basins.fold(0, |risk_level_sum, (_, height)| {
-            risk_level_sum + (*height as usize + 1)
-        })
+        input
+            .low_points()
+            .map(|index| input.heights[index] as usize + 1)
+            .sum()
     }
 
     /// A basin is all locations that eventually flow downward to a single low point.
@@ -57,31 +200,7 @@ impl Solver for Day09 {
     ///
     /// What do you get if you multiply together the sizes of the three largest basins?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let (heightmap, width) = input;
-        let length = heightmap.len() / width;
-        let basins = heightmap.iter().enumerate().filter(|(i, height)| {
-            adjacent_indices(*i, *width, length).all(|ii| **height < heightmap[ii])
-        });
-
-        let mut visited = vec![false; heightmap.len()];
-        let mut sizes = basins
-            .map(|(index, _)| {
-                let mut size = 0;
-                let mut dfs = vec![index];
-                while !dfs.is_empty() {
-                    let index = dfs.pop().unwrap();
-                    if !visited[index] {
-                        visited[index] = true;
-                        size += 1;
-                        dfs.extend(
-                            adjacent_indices(index, *width, length)
-                                .filter(|i| !visited[*i] && heightmap[*i] < 9),
-                        );
-                    }
-                }
-                size
-            })
-            .collect::<Vec<usize>>();
+        let mut sizes = input.basins(9, false).sizes;
 
         sizes.select_nth_unstable_by_key(2, |size| std::cmp::Reverse(*size));
         sizes[..3].iter().product()
@@ -91,12 +210,36 @@ impl Solver for Day09 {
         let lines = BufReader::new(r).lines().flatten().collect::<Vec<String>>();
         let width = lines.first().unwrap().len();
 
-        (
-            lines
+        Heightmap {
+            heights: lines
                 .iter()
                 .flat_map(|line| line.chars().map(|c| c as u8 - b'0').collect::<Vec<_>>())
                 .collect::<Vec<u8>>(),
             width,
-        )
+        }
+    }
+
+    fn report(&self, input: &Self::Input) {
+        if self.ridge.is_none() && !self.diagonal && !self.render && self.ppm.is_none() {
+            return;
+        }
+
+        let basins = input.basins(self.ridge.unwrap_or(9), self.diagonal);
+
+        let mut sizes = basins.sizes.clone();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        println!(
+            "{} basins, the largest of sizes {:?}",
+            sizes.len(),
+            &sizes[..sizes.len().min(3)]
+        );
+
+        if self.render {
+            print!("{}", render(input, &basins));
+        }
+
+        if let Some(path) = &self.ppm {
+            fs::write(path, ppm(input, &basins)).expect("unable to write PPM image");
+        }
     }
 }
@@ -1,44 +1,65 @@
 //! --- Day 10: Syntax Scoring ---
 
-use crate::solver::Solver;
-use std::io::{self, BufRead, BufReader};
+use crate::{
+    delimiters::{Language, Report, SyntaxError},
+    solver::Solver,
+};
+use std::io::{self, BufReader};
 
 /// https://adventofcode.com/2021/day/10
-pub struct Day10;
+pub struct Day10 {
+    /// Delimiters of each pair, one after the other (defaults to "()[]{}<>").
+    pub pairs: Option<String>,
+    /// Comma-separated points scored by an illegal closing delimiter of each pair.
+    pub error_points: Option<String>,
+    /// Comma-separated points scored by each closing delimiter in a completion string.
+    pub completion_points: Option<String>,
+    /// Print every error of each line, with its completion string or repair.
+    pub diagnose: bool,
+}
 
-enum SyntaxError {
-    /// `Vec<char>` is the sequence of unclosed chunks.
-    Incomplete(Vec<char>),
-    /// `char` is the first illegal character in the line.
-    Corrupted(char),
+fn parse_points(points: &str) -> Vec<u64> {
+    points
+        .split(',')
+        .map(|points| points.trim().parse().unwrap())
+        .collect()
 }
 
-fn syntax_error(chunks: &str) -> SyntaxError {
-    let mut openings = vec![];
-    for c in chunks.chars() {
-        if "([{<".contains(c) {
-            openings.push(c);
-        } else {
-            match openings.pop() {
-                Some(o) => match o {
-                    '(' if c != ')' => return SyntaxError::Corrupted(c),
-                    '[' if c != ']' => return SyntaxError::Corrupted(c),
-                    '{' if c != '}' => return SyntaxError::Corrupted(c),
-                    '<' if c != '>' => return SyntaxError::Corrupted(c),
-                    _ => {}
-                },
-                None => return SyntaxError::Corrupted(c),
+impl Day10 {
+    /// Returns the language given on the command line, where the points of the pairs
+    /// default to those of the navigation subsystem (or, for other pairs, to 1 for errors
+    /// and to the 1-based position of the pair for completions).
+    fn language(&self) -> Language {
+        let (pairs, error_points, completion_points) = match &self.pairs {
+            None => ("()[]{}<>", vec![3, 57, 1197, 25137], vec![1, 2, 3, 4]),
+            Some(pairs) => {
+                let pair_count = pairs.chars().count() as u64 / 2;
+                (
+                    pairs.as_str(),
+                    vec![1; pair_count as usize],
+                    (1..=pair_count).collect(),
+                )
             }
-        }
+        };
+
+        Language::from_table(
+            pairs,
+            &self
+                .error_points
+                .as_deref()
+                .map_or(error_points, parse_points),
+            &self
+                .completion_points
+                .as_deref()
+                .map_or(completion_points, parse_points),
+        )
     }
-    assert!(!openings.is_empty()); // otherwise it wouldn't be incomplete
-    SyntaxError::Incomplete(openings)
 }
 
 impl Solver for Day10 {
-    type Input = Vec<String>;
-    type Output1 = i32;
-    type Output2 = i64;
+    type Input = Vec<Report>;
+    type Output1 = u64;
+    type Output2 = u64;
 
     /// The navigation subsystem syntax is made of several lines containing chunks.
     /// Every chunk must open and close with one of four legal matching pairs: (), [], {}, <>.
@@ -55,19 +76,12 @@ impl Solver for Day10 {
     /// Find the first illegal character in each corrupted line of the navigation subsystem.
     /// What is the total syntax error score for those errors?
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
+        let language = self.language();
+
         input
             .iter()
-            .filter_map(|chunks| match syntax_error(chunks) {
-                SyntaxError::Incomplete(_) => None,
-                SyntaxError::Corrupted(chunk) => Some(chunk),
-            })
-            .map(|chunk| match chunk {
-                ')' => 3,
-                ']' => 57,
-                '}' => 1197,
-                '>' => 25137,
-                _ => unreachable!(),
-            })
+            .filter_map(Report::first_illegal)
+            .map(|chr| language.error_score(chr))
             .sum()
     }
 
@@ -87,31 +101,14 @@ impl Solver for Day10 {
     /// Autocomplete tools are an odd bunch: the winner is found by sorting all of the scores and
     /// then taking the middle score. (There will always be an odd number of scores to consider.)
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
+        let language = self.language();
+
         let mut scores = input
             .iter()
-            .filter_map(|chunks| match syntax_error(chunks) {
-                SyntaxError::Incomplete(open_chunks) => Some(open_chunks),
-                SyntaxError::Corrupted(_) => None,
-            })
-            .map(|open_chunks| {
-                open_chunks.into_iter().rev().map(|chunk| match chunk {
-                    '(' => ')',
-                    '[' => ']',
-                    '{' => '}',
-                    '<' => '>',
-                    _ => unreachable!(),
-                })
-            })
-            .map(|closing_chunks| {
-                closing_chunks.fold(0_i64, |score, chunk| match chunk {
-                    ')' => 5 * score + 1,
-                    ']' => 5 * score + 2,
-                    '}' => 5 * score + 3,
-                    '>' => 5 * score + 4,
-                    _ => unreachable!(),
-                })
-            })
-            .collect::<Vec<i64>>();
+            .filter_map(|report| report.completion.as_deref())
+            .filter(|completion| !completion.is_empty())
+            .map(|completion| language.completion_score(completion))
+            .collect::<Vec<u64>>();
 
         let median_index = scores.len() / 2; // 0-based
         scores.select_nth_unstable(median_index);
@@ -119,6 +116,47 @@ impl Solver for Day10 {
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
-        BufReader::new(r).lines().flatten().collect::<Vec<String>>()
+        self.language().check_lines(BufReader::new(r)).collect()
+    }
+
+    fn report(&self, input: &Self::Input) {
+        if !self.diagnose {
+            return;
+        }
+
+        for (line, report) in (1..).zip(input) {
+            for error in &report.errors {
+                match error {
+                    SyntaxError::Illegal {
+                        column,
+                        found,
+                        expected: Some(expected),
+                    } => println!(
+                        "{}:{}: expected {}, but found {} instead",
+                        line, column, expected, found
+                    ),
+                    SyntaxError::Illegal {
+                        column,
+                        found,
+                        expected: None,
+                    } => println!("{}:{}: {} doesn't close any chunk", line, column, found),
+                    SyntaxError::Unknown { column, found } => {
+                        println!("{}:{}: unknown delimiter {}", line, column, found)
+                    }
+                    SyntaxError::Unclosed { column, open } => {
+                        println!("{}:{}: {} is never closed", line, column, open)
+                    }
+                }
+            }
+
+            match (&report.completion, report.repair) {
+                (Some(completion), _) if !completion.is_empty() => {
+                    println!("{}: complete with {}", line, completion)
+                }
+                (_, Some(repair)) => println!("{}: repair with {:?}", line, repair),
+                _ if report.is_corrupted() => println!("{}: no single-character repair", line),
+                _ => {}
+            }
+        }
     }
 }
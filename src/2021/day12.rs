@@ -2,68 +2,140 @@
 
 use crate::solver::Solver;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     io::{self, BufRead, BufReader},
 };
 
 /// https://adventofcode.com/2021/day/12
-pub struct Day12;
-
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub enum Cave {
-    Start,
-    End,
-    Small(String),
-    Big(String),
+pub struct Day12 {
+    /// Count the paths on which up to this many small caves are visited twice.
+    pub twice: Option<usize>,
+    /// Print every path (on which up to `twice` small caves are visited twice).
+    pub paths: bool,
 }
 
-impl Cave {
-    fn new(cave: &str) -> Self {
-        if cave.chars().any(|c| c.is_uppercase()) {
-            Cave::Big(cave.to_string())
-        } else if cave == "start" {
-            Cave::Start
-        } else if cave == "end" {
-            Cave::End
+/// Caves, interned to ids, which are also the bits of small caves in visited sets.
+pub struct CaveSystem {
+    names: Vec<String>,
+    is_big: Vec<bool>,
+    connections: Vec<Vec<usize>>,
+    start: usize,
+    end: usize,
+}
+
+/// Sets of caves, as bitmasks of their ids.
+type Caves = u64;
+
+impl CaveSystem {
+    /// Returns the id of the cave named `name`, adding it if it's new.
+    fn intern(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(id) => id,
+            None => {
+                assert!(self.names.len() < Caves::BITS as usize, "too many caves");
+                self.names.push(name.to_string());
+                self.is_big.push(name.chars().any(|c| c.is_uppercase()));
+                self.connections.push(vec![]);
+                self.names.len() - 1
+            }
+        }
+    }
+
+    /// Returns the caves visited (at least once, and twice) after going to `next`,
+    /// if that's allowed when up to `twice` small caves may be visited twice
+    /// (but the start cave only once).
+    fn enter(
+        &self,
+        next: usize,
+        visited: Caves,
+        visited_twice: Caves,
+        twice: usize,
+    ) -> Option<(Caves, Caves)> {
+        let bit = 1 << next;
+
+        if self.is_big[next] {
+            Some((visited, visited_twice))
+        } else if visited & bit == 0 {
+            Some((visited | bit, visited_twice))
+        } else if next != self.start
+            && visited_twice & bit == 0
+            && (visited_twice.count_ones() as usize) < twice
+        {
+            Some((visited, visited_twice | bit))
         } else {
-            Cave::Small(cave.to_string())
+            None
         }
     }
-}
 
-fn paths_from(
-    cave: &Cave,
-    connections: &HashMap<Cave, Vec<Cave>>,
-    mut visited: HashSet<Cave>,
-    mut may_revisit: bool,
-) -> usize {
-    match *cave {
-        Cave::End => return 1,
-        Cave::Start => return 0,
-        Cave::Big(_) => {}
-        Cave::Small(_) => {
-            if !visited.insert(cave.clone()) {
-                if !may_revisit {
-                    return 0;
-                } else {
-                    may_revisit = false;
+    /// Returns the number of paths to the end cave from `cave`, memoized on the caves
+    /// visited so far, as the path that led there doesn't matter otherwise. Big caves
+    /// must not be connected to each other, or there would be infinitely many paths.
+    fn paths_from(
+        &self,
+        cave: usize,
+        visited: Caves,
+        visited_twice: Caves,
+        twice: usize,
+        memo: &mut HashMap<(usize, Caves, Caves), usize>,
+    ) -> usize {
+        if cave == self.end {
+            return 1;
+        }
+        if let Some(&paths) = memo.get(&(cave, visited, visited_twice)) {
+            return paths;
+        }
+
+        let paths = self.connections[cave]
+            .iter()
+            .map(
+                |&next| match self.enter(next, visited, visited_twice, twice) {
+                    Some((visited, visited_twice)) => {
+                        self.paths_from(next, visited, visited_twice, twice, memo)
+                    }
+                    None => 0,
+                },
+            )
+            .sum();
+
+        memo.insert((cave, visited, visited_twice), paths);
+        paths
+    }
+
+    /// Returns the number of paths from the start cave to the end cave.
+    fn count_paths(&self, twice: usize) -> usize {
+        self.paths_from(self.start, 1 << self.start, 0, twice, &mut HashMap::new())
+    }
+
+    /// Returns every path from the start cave to the end cave, as the names of its caves.
+    fn paths(&self, twice: usize) -> Vec<Vec<&str>> {
+        let mut paths = vec![];
+        let mut stack = vec![(vec![self.start], 1 << self.start, 0)];
+
+        while let Some((path, visited, visited_twice)) = stack.pop() {
+            let cave = *path.last().unwrap();
+            if cave == self.end {
+                paths.push(path.iter().map(|&id| self.names[id].as_str()).collect());
+                continue;
+            }
+
+            for &next in &self.connections[cave] {
+                if let Some((visited, visited_twice)) =
+                    self.enter(next, visited, visited_twice, twice)
+                {
+                    let mut path = path.clone();
+                    path.push(next);
+                    stack.push((path, visited, visited_twice));
                 }
             }
         }
-    }
 
-    connections.get(cave).map_or(0, |caves| {
-        caves
-            .iter()
-            .filter(|&next_cave| may_revisit || !visited.contains(next_cave))
-            .fold(0, |paths, next_cave| {
-                paths + paths_from(next_cave, connections, visited.clone(), may_revisit)
-            })
-    })
+        paths.sort_unstable();
+        paths
+    }
 }
 
 impl Solver for Day12 {
-    type Input = HashMap<Cave, Vec<Cave>>;
+    type Input = CaveSystem;
     type Output1 = usize;
     type Output2 = usize;
 
@@ -71,9 +143,7 @@ impl Solver for Day12 {
     /// end at end, and don't visit small caves more than once, and can visit
     /// big caves any number of times.
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        input[&Cave::Start].iter().fold(0, |paths, cave| {
-            paths + paths_from(cave, input, HashSet::new(), false)
-        })
+        input.count_paths(0)
     }
 
     /// After reviewing the available paths, you realize you might have time to
@@ -84,25 +154,45 @@ impl Solver for Day12 {
     ///
     /// Given these new rules, how many paths through this cave system are there?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        input[&Cave::Start].iter().fold(0, |paths, cave| {
-            paths + paths_from(cave, input, HashSet::new(), true)
-        })
+        input.count_paths(1)
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
-        let mut connections = HashMap::new();
+        let mut caves = CaveSystem {
+            names: vec![],
+            is_big: vec![],
+            connections: vec![],
+            start: 0,
+            end: 0,
+        };
+
         for line in BufReader::new(r).lines().flatten() {
             let (from, to) = line.split_once('-').unwrap();
-            connections
-                .entry(Cave::new(from))
-                .or_insert_with(Vec::new)
-                .push(Cave::new(to));
-            connections
-                .entry(Cave::new(to))
-                .or_insert_with(Vec::new)
-                .push(Cave::new(from));
+            let (from, to) = (caves.intern(from), caves.intern(to));
+            caves.connections[from].push(to);
+            caves.connections[to].push(from);
         }
+        caves.start = caves.intern("start");
+        caves.end = caves.intern("end");
 
-        connections
+        caves
+    }
+
+    fn report(&self, input: &Self::Input) {
+        let twice = self.twice.unwrap_or(0);
+
+        if self.twice.is_some() {
+            println!(
+                "{} paths visit up to {} small caves twice",
+                input.count_paths(twice),
+                twice
+            );
+        }
+
+        if self.paths {
+            for path in input.paths(twice) {
+                println!("{}", path.join(","));
+            }
+        }
     }
 }
@@ -1,137 +1,261 @@
 //! --- Day 14: Extended Polymerization ---
 
-use crate::solver::Solver;
+use crate::{
+    bigint::BigUint,
+    matrix::{Matrix, Modular, Ring},
+    solver::Solver,
+};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
     io::{self, BufRead, BufReader},
-    mem::swap,
 };
 
 /// https://adventofcode.com/2021/day/14
-pub struct Day14;
+pub struct Day14 {
+    /// Apply this many steps of pair insertion, printing the polymer's length and
+    /// the difference between its most and least common elements.
+    pub steps: Option<usize>,
+    /// Also print the quantity of each element after every one of the `steps`.
+    pub histogram: bool,
+    /// Also print the polymer itself after every one of the `steps`, while it's
+    /// (at most) this long.
+    pub materialize: Option<usize>,
+    /// Print the quantity of each element after this many steps, without
+    /// applying each one of them (so with `modulo`, it can be huge, like 10^18).
+    pub fast_forward: Option<u64>,
+    /// Count the fast-forwarded elements modulo this (instead of exactly, which
+    /// is only possible for up to `MAX_EXACT_STEPS` steps).
+    pub modulo: Option<u64>,
+}
+
+/// Most steps to fast-forward with exact counts, as the polymer's length doubles
+/// with each step (so that its number of digits grows linearly with the steps).
+const MAX_EXACT_STEPS: u64 = 1_000;
+
+type Rules = HashMap<(char, char), char>;
+
+/// Quantity of each pair of adjacent elements in a polymer, which is all we
+/// need to apply pair insertion (as its first element is always the same).
+struct Polymer<T> {
+    first: char,
+    pairs: Vec<(char, char)>,
+    /// Indices of the pairs that each pair becomes after a step of pair insertion:
+    /// two of them if there's a matching rule (with the element inserted in between),
+    /// or the same pair otherwise.
+    produces: Vec<Vec<usize>>,
+    counts: Vec<T>,
+    /// Whether each pair is in the polymer, tracked apart from `counts` as those
+    /// may be modular (where a count of 0 doesn't mean that the pair is missing).
+    present: Vec<bool>,
+    /// Elements that are in the polymer, as those are never removed from it.
+    elements: BTreeSet<char>,
+}
+
+impl<T: Ring> Polymer<T> {
+    /// Counts the pairs in `template` (including all pairs that `insertion_rules` may
+    /// produce), using the same kind of integer as `unit`.
+    fn new(template: &[char], insertion_rules: &Rules, unit: &T) -> Self {
+        let elements = template
+            .iter()
+            .copied()
+            .chain(insertion_rules.values().copied())
+            .chain(insertion_rules.keys().flat_map(|&(a, b)| [a, b]))
+            .collect::<BTreeSet<_>>();
+
+        let pairs = elements
+            .iter()
+            .flat_map(|&a| elements.iter().map(move |&b| (a, b)))
+            .collect::<Vec<_>>();
+        let index = |pair| pairs.iter().position(|&p| p == pair).unwrap();
+
+        let produces = pairs
+            .iter()
+            .map(|&(a, b)| match insertion_rules.get(&(a, b)) {
+                Some(&element) => vec![index((a, element)), index((element, b))],
+                None => vec![index((a, b))],
+            })
+            .collect();
+
+        let counts = pairs
+            .iter()
+            .map(|&pair| {
+                let count = template.windows(2).filter(|w| (w[0], w[1]) == pair).count();
+                unit.of(count as u64)
+            })
+            .collect();
+
+        let present = pairs
+            .iter()
+            .map(|&pair| template.windows(2).any(|w| (w[0], w[1]) == pair))
+            .collect();
+
+        Polymer {
+            first: template[0],
+            pairs,
+            produces,
+            counts,
+            present,
+            elements: template.iter().copied().collect(),
+        }
+    }
+
+    /// Marks the pairs that the present ones become after a step of pair insertion
+    /// (and their elements) as present instead.
+    fn step_present(&mut self) {
+        let mut present = vec![false; self.present.len()];
+        for (_, produces) in self.present.iter().zip(&self.produces).filter(|(&p, _)| p) {
+            for &pair in produces {
+                present[pair] = true;
+                let (a, b) = self.pairs[pair];
+                self.elements.extend([a, b]);
+            }
+        }
+
+        self.present = present;
+    }
+
+    /// Applies a single step of pair insertion.
+    fn step(&mut self) {
+        let mut counts = vec![self.counts[0].of(0); self.counts.len()];
+        for (count, produces) in self.counts.iter().zip(&self.produces) {
+            for &pair in produces {
+                counts[pair] = counts[pair].plus(count);
+            }
+        }
+
+        self.counts = counts;
+        self.step_present();
+    }
+
+    /// Applies `steps` steps of pair insertion at once, since each of them is the same
+    /// linear map (so it takes O(log steps) matrix multiplications).
+    fn fast_forward(&mut self, steps: u64) {
+        let unit = &self.counts[0];
+        let step = Matrix::from_fn(self.pairs.len(), |row, col| {
+            let produced = self.produces[col].iter().filter(|&&pair| pair == row);
+            unit.of(produced.count() as u64)
+        });
+
+        self.counts = step.pow(steps).apply(&self.counts);
+
+        // The present pairs eventually cycle, and then every element that can ever be
+        // in the polymer has been seen (so the remaining steps can be skipped).
+        let mut seen = HashSet::new();
+        for _ in 0..steps {
+            if !seen.insert(self.present.clone()) {
+                break;
+            }
+            self.step_present();
+        }
+    }
+
+    /// Returns the number of elements in the polymer (i.e. one more than its pairs).
+    fn len(&self) -> T {
+        let unit = self.counts[0].of(1);
+        self.counts.iter().fold(unit, |len, count| len.plus(count))
+    }
+
+    /// Returns the quantity of each element in the polymer, as every element is the
+    /// second one of some pair (except for the first element of the polymer).
+    fn element_counts(&self) -> Vec<(char, T)> {
+        let mut element_counts: Vec<(char, T)> = self
+            .elements
+            .iter()
+            .map(|&element| (element, self.counts[0].of(0)))
+            .collect();
+
+        for (&(_, element), count) in self.pairs.iter().zip(&self.counts) {
+            if let Some((_, total)) = element_counts.iter_mut().find(|(e, _)| *e == element) {
+                *total = total.plus(count);
+            }
+        }
+        if let Some((_, total)) = element_counts.iter_mut().find(|(e, _)| *e == self.first) {
+            *total = total.plus(&total.of(1));
+        }
+
+        element_counts
+    }
+}
+
+impl Polymer<BigUint> {
+    /// Returns the quantity of the most common element minus that of the least common one.
+    fn most_minus_least_common(&self) -> BigUint {
+        let element_counts = self.element_counts();
+        let most_count = element_counts.iter().map(|(_, count)| count).max().unwrap();
+        let least_count = element_counts.iter().map(|(_, count)| count).min().unwrap();
+
+        most_count.clone() - least_count
+    }
+}
+
+/// Returns the polymer after a step of pair insertion, by actually building it.
+fn insert_pairs(polymer: &[char], insertion_rules: &Rules) -> Vec<char> {
+    let mut inserted = Vec::with_capacity(2 * polymer.len());
+
+    inserted.push(polymer[0]);
+    for pair in polymer.windows(2) {
+        if let Some(&element) = insertion_rules.get(&(pair[0], pair[1])) {
+            inserted.push(element);
+        }
+        inserted.push(pair[1]);
+    }
+
+    inserted
+}
+
+fn histogram<T: Display>(element_counts: &[(char, T)]) -> String {
+    element_counts
+        .iter()
+        .map(|(element, count)| format!("{}: {}", element, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_fast_forward<T: Ring + Display>(
+    steps: u64,
+    template: &[char],
+    insertion_rules: &Rules,
+    unit: T,
+) {
+    let mut polymer = Polymer::new(template, insertion_rules, &unit);
+    polymer.fast_forward(steps);
+
+    println!("After {} steps: length {}", steps, polymer.len());
+    println!("  {}", histogram(&polymer.element_counts()));
+}
 
 impl Solver for Day14 {
-    type Input = (Vec<char>, HashMap<(char, char), char>);
-    type Output1 = i64;
-    type Output2 = i64;
+    type Input = (Vec<char>, Rules);
+    type Output1 = BigUint;
+    type Output2 = BigUint;
 
     /// Apply 10 steps of pair insertion to the polymer template and find the most and
     /// least common elements in the result. What do you get if you take the quantity
     /// of the most common element and subtract the quantity of the least common element?
     fn solve_part1(&self, input: &Self::Input) -> Self::Output1 {
-        let (polymer, insertion_rules) = input;
-
-        let mut element_count = {
-            let mut ping = polymer.clone();
-            let mut pong = Vec::with_capacity(ping.capacity());
-
-            for _ in 0..10 {
-                pong.clear();
-                pong.push(ping[0]);
-                for pair in ping.windows(2) {
-                    if let Some(&element) = insertion_rules.get(&(pair[0], pair[1])) {
-                        pong.push(element);
-                    }
-                    pong.push(pair[1]);
-                }
-                swap(&mut ping, &mut pong);
-            }
+        let (template, insertion_rules) = input;
 
-            ping.into_iter()
-                .fold(HashMap::<_, i64>::new(), |mut count, element| {
-                    *count.entry(element).or_default() += 1;
-                    count
-                })
-                .into_iter()
-        };
-
-        let (mut least_count, mut most_count) = {
-            let fst = element_count.next().map(|(_, count)| count).unwrap();
-            let snd = element_count.next().map(|(_, count)| count).unwrap();
-            if fst <= snd {
-                (fst, snd)
-            } else {
-                (snd, fst)
-            }
-        };
-
-        for (_, count) in element_count {
-            if count < least_count {
-                least_count = count;
-            } else if count > most_count {
-                most_count = count;
-            }
+        let mut polymer = Polymer::new(template, insertion_rules, &BigUint::from(1));
+        for _ in 0..10 {
+            polymer.step();
         }
 
-        most_count - least_count
+        polymer.most_minus_least_common()
     }
 
     /// Apply 40 steps of pair insertion to the polymer template and find the most and
     /// least common elements in the result. What do you get if you take the quantity
     /// of the most common element and subtract the quantity of the least common element?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        let (polymer, insertion_rules) = input;
-
-        let mut pair_count = {
-            let mut ping =
-                polymer
-                    .windows(2)
-                    .into_iter()
-                    .fold(HashMap::<_, i64>::new(), |mut count, pair| {
-                        let pair = (pair[0], pair[1]);
-                        *count.entry(pair).or_default() += 1;
-                        count
-                    });
-            let mut pong = HashMap::with_capacity(ping.capacity());
-
-            for _ in 0..40 {
-                pong.clear();
-                for (pair, count) in ping.iter() {
-                    if let Some(&element) = insertion_rules.get(pair) {
-                        *pong.entry((pair.0, element)).or_default() += count;
-                        *pong.entry((element, pair.1)).or_default() += count;
-                    } else {
-                        *pong.entry(*pair).or_default() += count;
-                    }
-                }
-                swap(&mut ping, &mut pong);
-            }
+        let (template, insertion_rules) = input;
 
-            ping.into_iter()
-        };
-
-        let mut element_count = {
-            let mut element_count = HashMap::new();
-            let (pair, count) = pair_count.next().unwrap();
-            element_count.insert(pair.0, count);
-
-            pair_count
-                .fold(element_count, |mut ecount, (pair, pcount)| {
-                    *ecount.entry(pair.1).or_default() += pcount;
-                    ecount
-                })
-                .into_iter()
-        };
-
-        let (mut least_count, mut most_count) = {
-            let fst = element_count.next().map(|(_, count)| count).unwrap();
-            let snd = element_count.next().map(|(_, count)| count).unwrap();
-            if fst <= snd {
-                (fst, snd)
-            } else {
-                (snd, fst)
-            }
-        };
-
-        for (_, count) in element_count {
-            if count < least_count {
-                least_count = count;
-            } else if count > most_count {
-                most_count = count;
-            }
+        let mut polymer = Polymer::new(template, insertion_rules, &BigUint::from(1));
+        for _ in 0..40 {
+            polymer.step();
         }
 
-        most_count - least_count
+        polymer.most_minus_least_common()
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
@@ -151,4 +275,49 @@ impl Solver for Day14 {
 
         (polymer_template, pair_insertion_rules)
     }
+
+    fn report(&self, input: &Self::Input) {
+        let (template, insertion_rules) = input;
+
+        if let Some(steps) = self.steps {
+            let mut polymer = Polymer::new(template, insertion_rules, &BigUint::from(1));
+            let mut materialized = self.materialize.map(|_| template.clone());
+
+            for step in 1..=steps {
+                polymer.step();
+
+                materialized = materialized
+                    .map(|materialized| insert_pairs(&materialized, insertion_rules))
+                    .filter(|materialized| Some(materialized.len()) <= self.materialize);
+
+                if self.histogram {
+                    println!("Step {}: {}", step, histogram(&polymer.element_counts()));
+                }
+                if let Some(materialized) = &materialized {
+                    println!("Step {}: {}", step, materialized.iter().collect::<String>());
+                }
+            }
+
+            println!(
+                "After {} steps: length {}, most minus least common {}",
+                steps,
+                polymer.len(),
+                polymer.most_minus_least_common()
+            );
+        }
+
+        if let Some(steps) = self.fast_forward {
+            match self.modulo {
+                Some(modulus) => {
+                    let unit = Modular::new(1, modulus);
+                    print_fast_forward(steps, template, insertion_rules, unit)
+                }
+                None if steps > MAX_EXACT_STEPS => println!(
+                    "Can't count the elements exactly after more than {} steps, use --modulo",
+                    MAX_EXACT_STEPS
+                ),
+                None => print_fast_forward(steps, template, insertion_rules, BigUint::from(1)),
+            }
+        }
+    }
 }
@@ -1,90 +1,502 @@
 //! --- Day 16: Packet Decoder ---
 
-use crate::solver::Solver;
+use crate::{bigint::BigUint, solver::Solver};
 use std::{
+    fmt::{self, Display},
     io::{self, BufRead, BufReader},
-    str,
 };
 
 /// https://adventofcode.com/2021/day/16
-pub struct Day16;
+pub struct Day16 {
+    /// Print the transmission as an expression.
+    pub expression: bool,
+    /// Print the transmission encoded back to hexadecimal, checking that it decodes
+    /// to the same packets.
+    pub encode: bool,
+    /// Decode this hexadecimal transmission (instead of the input) for the above.
+    pub decode: Option<String>,
+    /// Print this expression (as printed with `expression`) encoded to hexadecimal,
+    /// checking that it decodes back to the same expression.
+    pub encode_expression: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PacketError {
+    /// The transmission ends at `position`, before the `needed` bits of a field.
+    Truncated {
+        position: usize,
+        needed: usize,
+    },
+    /// The sub-packets of the packet at `position` span more bits than its length.
+    Overlong {
+        position: usize,
+    },
+    /// The operator packet at `position` doesn't have a valid number of sub-packets.
+    Operands {
+        position: usize,
+        operation: Operation,
+        count: usize,
+    },
+    InvalidHex(char),
+    /// The expression doesn't have what's `expected` at `position`.
+    Syntax {
+        position: usize,
+        expected: &'static str,
+    },
+}
+
+/// Reads bits, most significant first, from packed bytes.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    /// Number of bits that can be read.
+    len: usize,
+    /// Number of bits read so far.
+    position: usize,
+}
+
+impl BitReader<'_> {
+    /// Returns the next `bits` bits (at most 64) as an integer.
+    fn read(&mut self, bits: usize) -> Result<u64, PacketError> {
+        assert!(bits <= 64);
+        if self.position + bits > self.len {
+            return Err(PacketError::Truncated {
+                position: self.len,
+                needed: bits,
+            });
+        }
+
+        let mut value = 0;
+        for i in self.position..self.position + bits {
+            value = value << 1 | (self.bytes[i / 8] >> (7 - i % 8) & 1) as u64;
+        }
+        self.position += bits;
+
+        Ok(value)
+    }
+}
+
+/// Writes bits, most significant first, to packed bytes.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Number of bits written so far.
+    len: usize,
+}
 
-fn u64_from_utf8_radix_2(utf8_radix2: &[u8]) -> u64 {
-    let str_radix2 =
-        str::from_utf8(utf8_radix2).unwrap_or_else(|_| panic!("utf8_radix2={:?}", &utf8_radix2));
-    u64::from_str_radix(str_radix2, 2).unwrap_or_else(|_| panic!("str_radix2={:?}", &str_radix2))
+impl BitWriter {
+    /// Writes the lowest `bits` bits of `value`.
+    fn write(&mut self, value: u64, bits: usize) {
+        for i in (0..bits).rev() {
+            if self.len == 8 * self.bytes.len() {
+                self.bytes.push(0);
+            }
+            self.bytes[self.len / 8] |= ((value >> i & 1) as u8) << (7 - self.len % 8);
+            self.len += 1;
+        }
+    }
+
+    fn append(&mut self, other: &BitWriter) {
+        let mut reader = BitReader {
+            bytes: &other.bytes,
+            len: other.len,
+            position: 0,
+        };
+        for _ in 0..other.len {
+            self.write(reader.read(1).unwrap(), 1);
+        }
+    }
+
+    /// Returns the bits written in hexadecimal, padded with zeros to a whole digit.
+    fn to_hex(&self) -> String {
+        let hex = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<String>();
+
+        hex[..self.len.div_ceil(4)].to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operation {
+    Sum,
+    Product,
+    Minimum,
+    Maximum,
+    GreaterThan,
+    LessThan,
+    EqualTo,
 }
 
-#[derive(Debug)]
-struct Packet {
+/// How an operator packet gives the extent of its sub-packets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LengthType {
+    /// Total length in bits of the sub-packets.
+    Bits,
+    /// Number of sub-packets.
+    Count,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
     version: u64,
     kind: PacketKind,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum PacketKind {
-    LiteralValue(u64),
-    Operator(u64, Vec<Packet>),
+    LiteralValue(BigUint),
+    Operator(Operation, LengthType, Vec<Packet>),
+}
+
+const LITERAL_VALUE_TYPE_ID: u64 = 4;
+
+impl Operation {
+    fn from_type_id(type_id: u64) -> Option<Self> {
+        match type_id {
+            0 => Some(Operation::Sum),
+            1 => Some(Operation::Product),
+            2 => Some(Operation::Minimum),
+            3 => Some(Operation::Maximum),
+            5 => Some(Operation::GreaterThan),
+            6 => Some(Operation::LessThan),
+            7 => Some(Operation::EqualTo),
+            _ => None,
+        }
+    }
+
+    fn type_id(self) -> u64 {
+        match self {
+            Operation::Sum => 0,
+            Operation::Product => 1,
+            Operation::Minimum => 2,
+            Operation::Maximum => 3,
+            Operation::GreaterThan => 5,
+            Operation::LessThan => 6,
+            Operation::EqualTo => 7,
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Operation::GreaterThan | Operation::LessThan | Operation::EqualTo
+        )
+    }
 }
 
-fn parse_packet(packet: &[u8]) -> (Packet, usize) {
-    let version = u64_from_utf8_radix_2(&packet[0..3]);
-    let type_id = u64_from_utf8_radix_2(&packet[3..6]);
+fn parse_packet(reader: &mut BitReader) -> Result<Packet, PacketError> {
+    let position = reader.position;
+    let version = reader.read(3)?;
+    let type_id = reader.read(3)?;
 
-    let mut packet_length = 6;
+    let kind = match Operation::from_type_id(type_id) {
+        None => {
+            // Groups of 4 bits, each prefixed by 1 except for the last one.
+            let mut value = BigUint::default();
+            loop {
+                let group = reader.read(5)?;
+                value = &value * &BigUint::from(16) + &BigUint::from(group & 0b1111);
+                if group >> 4 == 0 {
+                    break;
+                }
+            }
+            PacketKind::LiteralValue(value)
+        }
+        Some(operation) => {
+            let mut subpackets = vec![];
+            let length_type = match reader.read(1)? {
+                0 => {
+                    let end = reader.read(15)? as usize + reader.position;
+                    while reader.position < end {
+                        subpackets.push(parse_packet(reader)?);
+                    }
+                    if reader.position > end {
+                        return Err(PacketError::Overlong { position });
+                    }
+                    LengthType::Bits
+                }
+                _ => {
+                    for _ in 0..reader.read(11)? {
+                        subpackets.push(parse_packet(reader)?);
+                    }
+                    LengthType::Count
+                }
+            };
 
-    let kind = if type_id == 4 {
-        let mut value = vec![];
-        for chunk in packet[6..].chunks_exact(5) {
-            packet_length += 5;
-            value.extend_from_slice(&chunk[1..]);
-            if chunk[0] == b'0' {
-                break;
+            let count = subpackets.len();
+            if count == 0 || (operation.is_comparison() && count != 2) {
+                return Err(PacketError::Operands {
+                    position,
+                    operation,
+                    count,
+                });
             }
+            PacketKind::Operator(operation, length_type, subpackets)
         }
-        PacketKind::LiteralValue(u64_from_utf8_radix_2(&value))
-    } else {
-        let length_type_id = packet[6];
-        packet_length += 1;
-
-        match length_type_id {
-            b'0' => {
-                // Total length in bits of the sub-packets contained by this packet.
-                let subpackets_length = u64_from_utf8_radix_2(&packet[7..7 + 15]);
-                packet_length += 15;
-
-                let mut subpackets = vec![];
-                while packet_length < subpackets_length as usize + 7 + 15 {
-                    let (subpacket, subpacket_length) = parse_packet(&packet[packet_length..]);
-                    packet_length += subpacket_length;
-                    subpackets.push(subpacket);
+    };
+
+    Ok(Packet { version, kind })
+}
+
+fn write_packet(packet: &Packet, writer: &mut BitWriter) {
+    writer.write(packet.version, 3);
+
+    match &packet.kind {
+        PacketKind::LiteralValue(value) => {
+            writer.write(LITERAL_VALUE_TYPE_ID, 3);
+
+            let mut groups = vec![];
+            let mut value = value.clone();
+            loop {
+                let (quotient, group) = value.div_rem(16);
+                groups.push(group as u64);
+                value = quotient;
+                if value == BigUint::default() {
+                    break;
                 }
-                PacketKind::Operator(type_id, subpackets)
             }
-            b'1' => {
-                // Number of sub-packets immediately contained by this packet.
-                let number_of_subpackets = u64_from_utf8_radix_2(&packet[7..7 + 11]);
-                packet_length += 11;
-
-                let mut subpackets = vec![];
-                while subpackets.len() < number_of_subpackets as usize {
-                    let (subpacket, subpacket_length) = parse_packet(&packet[packet_length..]);
-                    packet_length += subpacket_length;
-                    subpackets.push(subpacket);
+            for (i, &group) in groups.iter().enumerate().rev() {
+                writer.write(((i > 0) as u64) << 4 | group, 5);
+            }
+        }
+        PacketKind::Operator(operation, length_type, subpackets) => {
+            writer.write(operation.type_id(), 3);
+
+            match length_type {
+                LengthType::Bits => {
+                    let mut subwriter = BitWriter::default();
+                    for subpacket in subpackets {
+                        write_packet(subpacket, &mut subwriter);
+                    }
+                    assert!(subwriter.len < 1 << 15, "sub-packets too long");
+                    writer.write(0, 1);
+                    writer.write(subwriter.len as u64, 15);
+                    writer.append(&subwriter);
+                }
+                LengthType::Count => {
+                    assert!(subpackets.len() < 1 << 11, "too many sub-packets");
+                    writer.write(1, 1);
+                    writer.write(subpackets.len() as u64, 11);
+                    for subpacket in subpackets {
+                        write_packet(subpacket, writer);
+                    }
                 }
-                PacketKind::Operator(type_id, subpackets)
             }
-            _ => unreachable!(),
         }
+    }
+}
+
+/// Returns the outermost packet of a hexadecimal transmission (ignoring any padding).
+fn decode(transmission: &str) -> Result<Packet, PacketError> {
+    let digits = transmission
+        .chars()
+        .map(|hex| hex.to_digit(16).ok_or(PacketError::InvalidHex(hex)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let bytes = digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4 | pair.get(1).copied().unwrap_or(0)) as u8)
+        .collect::<Vec<_>>();
+
+    parse_packet(&mut BitReader {
+        bytes: &bytes,
+        len: 4 * digits.len(),
+        position: 0,
+    })
+}
+
+/// Returns the hexadecimal transmission of `packet`.
+fn encode(packet: &Packet) -> String {
+    let mut writer = BitWriter::default();
+    write_packet(packet, &mut writer);
+
+    writer.to_hex()
+}
+
+/// Parses expressions in the format of `Display for Packet`, into packets of version 0
+/// which give the number of their sub-packets (as the format doesn't have either).
+struct ExpressionParser<'a> {
+    expression: &'a str,
+    /// Number of bytes parsed so far.
+    position: usize,
+}
+
+impl ExpressionParser<'_> {
+    fn rest(&self) -> &str {
+        &self.expression[self.position..]
+    }
+
+    /// Skips `token` if the rest of the expression starts with it.
+    fn skip(&mut self, token: &str) -> bool {
+        let starts_with = self.rest().starts_with(token);
+        if starts_with {
+            self.position += token.len();
+        }
+        starts_with
+    }
+
+    fn expect(&mut self, token: &'static str) -> Result<(), PacketError> {
+        match self.skip(token) {
+            true => Ok(()),
+            false => Err(PacketError::Syntax {
+                position: self.position,
+                expected: token,
+            }),
+        }
+    }
+
+    fn parse_packet(&mut self) -> Result<Packet, PacketError> {
+        let position = self.position;
+
+        let digits = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+        if digits > 0 {
+            let value = self.rest()[..digits]
+                .bytes()
+                .fold(BigUint::default(), |n, digit| {
+                    &n * &BigUint::from(10) + &BigUint::from((digit - b'0') as u64)
+                });
+            self.position += digits;
+            return Ok(Packet {
+                version: 0,
+                kind: PacketKind::LiteralValue(value),
+            });
+        }
+
+        // Functions separate their operands with commas, and others with their operator
+        // (where a single operand in parentheses is a sum).
+        let mut operation = if self.skip("min(") {
+            Some(Operation::Minimum)
+        } else if self.skip("max(") {
+            Some(Operation::Maximum)
+        } else if self.skip("(") {
+            None
+        } else {
+            return Err(PacketError::Syntax {
+                position,
+                expected: "a number, \"(\", \"min(\" or \"max(\"",
+            });
+        };
+
+        let mut subpackets = vec![self.parse_packet()?];
+        while !self.skip(")") {
+            let separator = match operation {
+                Some(Operation::Minimum | Operation::Maximum) => ", ",
+                Some(Operation::Sum) => " + ",
+                Some(Operation::Product) => " * ",
+                Some(Operation::GreaterThan) => " > ",
+                Some(Operation::LessThan) => " < ",
+                Some(Operation::EqualTo) => " == ",
+                None => {
+                    let operators = [
+                        (" + ", Operation::Sum),
+                        (" * ", Operation::Product),
+                        (" > ", Operation::GreaterThan),
+                        (" < ", Operation::LessThan),
+                        (" == ", Operation::EqualTo),
+                    ];
+                    let &(operator, found) = operators
+                        .iter()
+                        .find(|(operator, _)| self.rest().starts_with(operator))
+                        .ok_or(PacketError::Syntax {
+                            position: self.position,
+                            expected: "an operator or \")\"",
+                        })?;
+                    operation = Some(found);
+                    operator
+                }
+            };
+            self.expect(separator)?;
+            subpackets.push(self.parse_packet()?);
+        }
+
+        let operation = operation.unwrap_or(Operation::Sum);
+        let count = subpackets.len();
+        if operation.is_comparison() && count != 2 {
+            return Err(PacketError::Operands {
+                position,
+                operation,
+                count,
+            });
+        }
+
+        Ok(Packet {
+            version: 0,
+            kind: PacketKind::Operator(operation, LengthType::Count, subpackets),
+        })
+    }
+}
+
+/// Returns the packet of an expression, in the format of `Display for Packet`.
+fn parse_expression(expression: &str) -> Result<Packet, PacketError> {
+    let mut parser = ExpressionParser {
+        expression,
+        position: 0,
     };
+    let packet = parser.parse_packet()?;
+    if !parser.rest().is_empty() {
+        return Err(PacketError::Syntax {
+            position: parser.position,
+            expected: "the end of the expression",
+        });
+    }
 
-    (Packet { version, kind }, packet_length)
+    Ok(packet)
+}
+
+impl Packet {
+    fn eval(&self) -> BigUint {
+        match &self.kind {
+            PacketKind::LiteralValue(value) => value.clone(),
+            PacketKind::Operator(operation, _, subpackets) => {
+                let mut values = subpackets.iter().map(Packet::eval);
+                match operation {
+                    Operation::Sum => values.sum(),
+                    Operation::Product => values.fold(BigUint::from(1), |acc, v| &acc * &v),
+                    Operation::Minimum => values.min().unwrap(),
+                    Operation::Maximum => values.max().unwrap(),
+                    Operation::GreaterThan => BigUint::from((values.next() > values.next()) as u64),
+                    Operation::LessThan => BigUint::from((values.next() < values.next()) as u64),
+                    Operation::EqualTo => BigUint::from((values.next() == values.next()) as u64),
+                }
+            }
+        }
+    }
+}
+
+impl Display for Packet {
+    /// Formats the packet as an expression, e.g. "(1 + min(2, 3) * 4)".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (operation, subpackets) = match &self.kind {
+            PacketKind::LiteralValue(value) => return write!(f, "{}", value),
+            PacketKind::Operator(operation, _, subpackets) => (operation, subpackets),
+        };
+
+        let (prefix, separator) = match operation {
+            Operation::Sum => ("(", " + "),
+            Operation::Product => ("(", " * "),
+            Operation::Minimum => ("min(", ", "),
+            Operation::Maximum => ("max(", ", "),
+            Operation::GreaterThan => ("(", " > "),
+            Operation::LessThan => ("(", " < "),
+            Operation::EqualTo => ("(", " == "),
+        };
+
+        write!(f, "{}", prefix)?;
+        for (i, subpacket) in subpackets.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", separator)?;
+            }
+            write!(f, "{}", subpacket)?;
+        }
+        write!(f, ")")
+    }
 }
 
 impl Solver for Day16 {
-    type Input = Vec<u8>;
+    type Input = Packet;
     type Output1 = u64;
-    type Output2 = u64;
+    type Output2 = BigUint;
 
     /// Decode the structure of your hexadecimal-encoded BITS transmission;
     /// what do you get if you add up the version numbers in all packets?
@@ -92,7 +504,7 @@ impl Solver for Day16 {
         fn sum_versions(packet: &Packet) -> u64 {
             match packet.kind {
                 PacketKind::LiteralValue(_) => packet.version,
-                PacketKind::Operator(_, ref subpackets) => {
+                PacketKind::Operator(_, _, ref subpackets) => {
                     subpackets.iter().fold(packet.version, |acc, subpacket| {
                         acc + sum_versions(subpacket)
                     })
@@ -100,55 +512,53 @@ impl Solver for Day16 {
             }
         }
 
-        sum_versions(&parse_packet(input).0)
+        sum_versions(input)
     }
 
     /// What do you get if you evaluate the expression represented by your
     /// hexadecimal-encoded BITS transmission?
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2 {
-        fn eval(packet: &Packet) -> u64 {
-            match packet.kind {
-                PacketKind::LiteralValue(value) => value,
-                PacketKind::Operator(type_id, ref subpackets) => match type_id {
-                    0 => subpackets.iter().fold(0, |acc, p| acc + eval(p)),
-                    1 => subpackets.iter().fold(1, |acc, p| acc * eval(p)),
-                    2 => subpackets.iter().fold(u64::MAX, |acc, p| acc.min(eval(p))),
-                    3 => subpackets.iter().fold(u64::MIN, |acc, p| acc.max(eval(p))),
-                    5 => (eval(&subpackets[0]) > eval(&subpackets[1])) as u64,
-                    6 => (eval(&subpackets[0]) < eval(&subpackets[1])) as u64,
-                    7 => (eval(&subpackets[0]) == eval(&subpackets[1])) as u64,
-                    _ => unreachable!(),
-                },
-            }
-        }
-
-        eval(&parse_packet(input).0)
+        input.eval()
     }
 
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input {
         let input = BufReader::new(r).lines().flatten().next().unwrap();
 
-        input
-            .chars()
-            .flat_map(|hex| match hex {
-                '0' => [b'0', b'0', b'0', b'0'],
-                '1' => [b'0', b'0', b'0', b'1'],
-                '2' => [b'0', b'0', b'1', b'0'],
-                '3' => [b'0', b'0', b'1', b'1'],
-                '4' => [b'0', b'1', b'0', b'0'],
-                '5' => [b'0', b'1', b'0', b'1'],
-                '6' => [b'0', b'1', b'1', b'0'],
-                '7' => [b'0', b'1', b'1', b'1'],
-                '8' => [b'1', b'0', b'0', b'0'],
-                '9' => [b'1', b'0', b'0', b'1'],
-                'A' => [b'1', b'0', b'1', b'0'],
-                'B' => [b'1', b'0', b'1', b'1'],
-                'C' => [b'1', b'1', b'0', b'0'],
-                'D' => [b'1', b'1', b'0', b'1'],
-                'E' => [b'1', b'1', b'1', b'0'],
-                'F' => [b'1', b'1', b'1', b'1'],
-                _ => unreachable!(),
-            })
-            .collect::<Vec<u8>>()
+        decode(input.trim()).unwrap()
+    }
+
+    fn report(&self, input: &Self::Input) {
+        let packet = match &self.decode {
+            Some(transmission) => match decode(transmission) {
+                Ok(packet) => packet,
+                Err(err) => return println!("{:?}", err),
+            },
+            None => input.clone(),
+        };
+
+        if self.expression {
+            println!("{} = {}", packet, packet.eval());
+        }
+
+        if self.encode {
+            let transmission = encode(&packet);
+            println!("{}", transmission);
+            if decode(&transmission).as_ref() != Ok(&packet) {
+                println!("The encoded transmission doesn't decode to the same packets");
+            }
+        }
+
+        if let Some(expression) = &self.encode_expression {
+            let packet = match parse_expression(expression) {
+                Ok(packet) => packet,
+                Err(err) => return println!("{:?}", err),
+            };
+            let transmission = encode(&packet);
+            println!("{}", transmission);
+            match decode(&transmission) {
+                Ok(decoded) if decoded.to_string() == *expression => {}
+                _ => println!("The encoded transmission doesn't decode to the same expression"),
+            }
+        }
     }
 }
@@ -0,0 +1,269 @@
+//! One-to-one assignment of "left" items to "right" items (i.e. bipartite matching).
+//!
+//! Each left item has a list of candidate right items (by index), and an assignment
+//! maps every left item to a distinct one of its candidates. There may be more right
+//! items than left ones, in which case some right items are simply left unassigned.
+
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssignmentError {
+    /// The `left` items can only be assigned to the `right` items, and there are
+    /// fewer of those, so there's no consistent assignment for all of them.
+    Contradictory { left: Vec<usize>, right: Vec<usize> },
+
+    /// There's more than one consistent assignment, as each of the listed left
+    /// items can be assigned to any of its (viable) right items.
+    Ambiguous(Vec<(usize, Vec<usize>)>),
+}
+
+/// Removes the candidate of every left item that has a single one from all others,
+/// repeating until no more candidates can be eliminated.
+pub fn propagate(candidates: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut candidates = candidates.to_vec();
+    let mut is_settled = vec![false; candidates.len()];
+
+    while let Some(settled) =
+        (0..candidates.len()).find(|&l| !is_settled[l] && candidates[l].len() == 1)
+    {
+        is_settled[settled] = true;
+        let right = candidates[settled][0];
+        for (l, rights) in candidates.iter_mut().enumerate() {
+            if l != settled {
+                rights.retain(|&r| r != right);
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Returns a maximum matching, as the right item (if any) assigned to each left item.
+///
+/// Ref.: https://en.wikipedia.org/wiki/Hopcroft%E2%80%93Karp_algorithm
+pub fn hopcroft_karp(candidates: &[Vec<usize>], right_count: usize) -> Vec<Option<usize>> {
+    const UNREACHED: usize = usize::MAX;
+
+    fn augment(
+        l: usize,
+        candidates: &[Vec<usize>],
+        dist: &mut [usize],
+        match_left: &mut [Option<usize>],
+        match_right: &mut [Option<usize>],
+    ) -> bool {
+        for &r in &candidates[l] {
+            let is_augmenting = match match_right[r] {
+                None => true,
+                Some(next) => {
+                    dist[next] == dist[l] + 1
+                        && augment(next, candidates, dist, match_left, match_right)
+                }
+            };
+            if is_augmenting {
+                match_left[l] = Some(r);
+                match_right[r] = Some(l);
+                return true;
+            }
+        }
+
+        dist[l] = UNREACHED;
+        false
+    }
+
+    let left_count = candidates.len();
+    let mut match_left = vec![None; left_count];
+    let mut match_right = vec![None; right_count];
+    let mut dist = vec![UNREACHED; left_count];
+
+    loop {
+        // Layer the left items by their (alternating path) distance to a free one.
+        let mut queue = VecDeque::new();
+        for (l, matched) in match_left.iter().enumerate() {
+            dist[l] = if matched.is_none() { 0 } else { UNREACHED };
+            if matched.is_none() {
+                queue.push_back(l);
+            }
+        }
+
+        let mut has_augmenting_path = false;
+        while let Some(l) = queue.pop_front() {
+            for &r in &candidates[l] {
+                match match_right[r] {
+                    None => has_augmenting_path = true,
+                    Some(next) if dist[next] == UNREACHED => {
+                        dist[next] = dist[l] + 1;
+                        queue.push_back(next);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if !has_augmenting_path {
+            break match_left;
+        }
+
+        for l in 0..left_count {
+            if match_left[l].is_none() {
+                augment(l, candidates, &mut dist, &mut match_left, &mut match_right);
+            }
+        }
+    }
+}
+
+/// Returns the left items that can't all be assigned, and the right items they're
+/// restricted to, given a maximum `matching` that leaves some left item unassigned.
+fn contradiction(
+    candidates: &[Vec<usize>],
+    right_count: usize,
+    matching: &[Option<usize>],
+) -> AssignmentError {
+    let mut match_right = vec![None; right_count];
+    for (l, r) in matching.iter().enumerate() {
+        if let Some(r) = *r {
+            match_right[r] = Some(l);
+        }
+    }
+
+    // Since `matching` is maximum, every right item reached through an alternating
+    // path from an unassigned left item is matched (otherwise we could augment it),
+    // so the reached left items outnumber the right ones by one (Hall's theorem).
+    let unassigned = matching.iter().position(Option::is_none).unwrap();
+    let mut left_reached = vec![false; candidates.len()];
+    let mut right_reached = vec![false; right_count];
+    left_reached[unassigned] = true;
+
+    let mut stack = vec![unassigned];
+    while let Some(l) = stack.pop() {
+        for &r in &candidates[l] {
+            if !right_reached[r] {
+                right_reached[r] = true;
+                if let Some(next) = match_right[r].filter(|&next| !left_reached[next]) {
+                    left_reached[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    let reached = |is_reached: Vec<bool>| {
+        is_reached
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, is_reached)| if is_reached { Some(i) } else { None })
+            .collect()
+    };
+
+    AssignmentError::Contradictory {
+        left: reached(left_reached),
+        right: reached(right_reached),
+    }
+}
+
+/// Returns the only consistent assignment, as the right item assigned to each left item,
+/// or which candidates make it contradictory (no assignment) or ambiguous (more than one).
+pub fn unique_assignment(
+    candidates: &[Vec<usize>],
+    right_count: usize,
+) -> Result<Vec<usize>, AssignmentError> {
+    let candidates = propagate(candidates);
+
+    let matching = hopcroft_karp(&candidates, right_count);
+    if matching.iter().any(Option::is_none) {
+        return Err(contradiction(&candidates, right_count, &matching));
+    }
+
+    // Keep only the candidates that are part of some (full) assignment, that is,
+    // those for which the other left items can still be assigned after picking it.
+    let left_count = candidates.len();
+    let viable = (0..left_count)
+        .map(|l| {
+            candidates[l]
+                .iter()
+                .copied()
+                .filter(|&r| {
+                    if matching[l] == Some(r) {
+                        return true;
+                    }
+                    let others = (0..left_count)
+                        .map(|other| {
+                            if other == l {
+                                vec![]
+                            } else {
+                                let rights = candidates[other].iter().copied();
+                                rights.filter(|&o| o != r).collect()
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    hopcroft_karp(&others, right_count)
+                        .into_iter()
+                        .filter(Option::is_some)
+                        .count()
+                        == left_count - 1
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let ambiguous = viable
+        .into_iter()
+        .enumerate()
+        .filter(|(_, rights)| rights.len() > 1)
+        .collect::<Vec<_>>();
+
+    if ambiguous.is_empty() {
+        Ok(matching.into_iter().flatten().collect())
+    } else {
+        Err(AssignmentError::Ambiguous(ambiguous))
+    }
+}
+
+/// Returns every consistent assignment, as the right item assigned to each left item.
+pub fn all_assignments(candidates: &[Vec<usize>], right_count: usize) -> Vec<Vec<usize>> {
+    fn backtrack(
+        order: &[usize],
+        candidates: &[Vec<usize>],
+        is_used: &mut [bool],
+        assignment: &mut [usize],
+        assignments: &mut Vec<Vec<usize>>,
+    ) {
+        match order.split_first() {
+            None => assignments.push(assignment.to_vec()),
+            Some((&l, order)) => {
+                for &r in &candidates[l] {
+                    if !is_used[r] {
+                        is_used[r] = true;
+                        assignment[l] = r;
+                        backtrack(order, candidates, is_used, assignment, assignments);
+                        is_used[r] = false;
+                    }
+                }
+            }
+        }
+    }
+
+    let candidates = propagate(candidates);
+
+    // There's nothing to enumerate if not even a maximum matching assigns everything.
+    if hopcroft_karp(&candidates, right_count)
+        .iter()
+        .any(Option::is_none)
+    {
+        return vec![];
+    }
+
+    // Branch on the most constrained left items first, to prune earlier.
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&l| candidates[l].len());
+
+    let mut assignments = vec![];
+    backtrack(
+        &order,
+        &candidates,
+        &mut vec![false; right_count],
+        &mut vec![0; candidates.len()],
+        &mut assignments,
+    );
+
+    assignments
+}
@@ -0,0 +1,176 @@
+//! Arbitrary-precision unsigned integers, for counts that would overflow `u64`.
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Sub},
+};
+
+/// Each limb holds 9 decimal digits, so that printing doesn't need divisions.
+const BASE: u64 = 1_000_000_000;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    /// Little-endian digits in base `BASE`, without leading zeros (so 0 has none).
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    /// Returns the quotient and remainder of the division of `self` by `divisor`.
+    pub fn div_rem(&self, divisor: u32) -> (BigUint, u32) {
+        assert!(divisor > 0, "attempt to divide by zero");
+
+        let mut remainder = 0;
+        let mut quotient = self.clone();
+        for limb in quotient.limbs.iter_mut().rev() {
+            let dividend = remainder * BASE + *limb as u64;
+            *limb = (dividend / divisor as u64) as u32;
+            remainder = dividend % divisor as u64;
+        }
+        quotient.trim();
+
+        (quotient, remainder as u32)
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(mut n: u64) -> Self {
+        let mut limbs = vec![];
+        while n > 0 {
+            limbs.push((n % BASE) as u32);
+            n /= BASE;
+        }
+
+        BigUint { limbs }
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, other: &BigUint) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+
+        let mut carry = 0;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb as u64 + other.limbs.get(i).copied().unwrap_or(0) as u64 + carry;
+            *limb = (sum % BASE) as u32;
+            carry = sum / BASE;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+impl Add<&BigUint> for BigUint {
+    type Output = BigUint;
+
+    fn add(mut self, other: &BigUint) -> BigUint {
+        self += other;
+        self
+    }
+}
+
+impl Sub<&BigUint> for BigUint {
+    type Output = BigUint;
+
+    /// Panics if `other` is greater than `self` (as the result would be negative).
+    fn sub(mut self, other: &BigUint) -> BigUint {
+        assert!(*other <= self, "attempt to subtract with overflow");
+
+        let mut borrow = 0;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let subtrahend = other.limbs.get(i).copied().unwrap_or(0) as u64 + borrow;
+            let (limb_value, next_borrow) = match (*limb as u64).checked_sub(subtrahend) {
+                Some(diff) => (diff, 0),
+                None => (*limb as u64 + BASE - subtrahend, 1),
+            };
+            *limb = limb_value as u32;
+            borrow = next_borrow;
+        }
+        self.trim();
+
+        self
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.limbs.is_empty() || other.limbs.is_empty() {
+            return BigUint::default();
+        }
+
+        // Schoolbook multiplication, carrying after each row so that the
+        // accumulated products can't overflow the `u64` intermediate values.
+        let mut limbs = vec![0_u64; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = limbs[i + j] + a as u64 * b as u64 + carry;
+                limbs[i + j] = product % BASE;
+                carry = product / BASE;
+            }
+            limbs[i + other.limbs.len()] += carry;
+        }
+
+        let mut product = BigUint {
+            limbs: limbs.into_iter().map(|limb| limb as u32).collect(),
+        };
+        product.trim();
+
+        product
+    }
+}
+
+impl Sum for BigUint {
+    fn sum<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::default(), |acc, n| acc + &n)
+    }
+}
+
+impl<'a> Sum<&'a BigUint> for BigUint {
+    fn sum<I: Iterator<Item = &'a BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::default(), |acc, n| acc + n)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        match limbs.next() {
+            None => f.pad("0"),
+            Some(most_significant) => {
+                let mut digits = most_significant.to_string();
+                for limb in limbs {
+                    digits.push_str(&format!("{:09}", limb));
+                }
+                f.pad(&digits)
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Languages of balanced delimiters (e.g. brackets), where every chunk opened by one
+//! delimiter of a pair must be closed by the other one, and chunks may be nested.
+//!
+//! Both the pairs and the points scored by their errors and completions are configurable.
+
+use std::io::BufRead;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pair {
+    pub open: char,
+    pub close: char,
+    /// Points scored by an illegal `close`.
+    pub error_points: u64,
+    /// Points scored by each `close` in a completion string.
+    pub completion_points: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Language {
+    pairs: Vec<Pair>,
+    /// Completion scores are multiplied by this before adding the points of each character.
+    completion_base: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyntaxError {
+    /// A closing delimiter that doesn't match the innermost open chunk, or that
+    /// doesn't close anything at all (if `expected` is `None`).
+    Illegal {
+        column: usize,
+        found: char,
+        expected: Option<char>,
+    },
+    /// A character that isn't a delimiter of the language.
+    Unknown { column: usize, found: char },
+    /// A chunk that is opened at `column` but never closed.
+    Unclosed { column: usize, open: char },
+}
+
+/// An edit of a single character, which makes a corrupted line merely incomplete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repair {
+    Replace { column: usize, with: char },
+    Insert { column: usize, chr: char },
+    Delete { column: usize },
+}
+
+/// Result of checking a line (where columns are 1-based).
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    /// Every error, in the order they were found. After an illegal closing delimiter,
+    /// checking resumes as if it had closed the innermost open chunk anyway.
+    pub errors: Vec<SyntaxError>,
+    /// Shortest string closing every open chunk, if the line isn't corrupted.
+    pub completion: Option<String>,
+    /// Single-character edit after which the line isn't corrupted, if there's one.
+    pub repair: Option<Repair>,
+}
+
+impl SyntaxError {
+    /// Returns true iff the error can't be fixed by appending characters.
+    pub fn is_corrupting(&self) -> bool {
+        !matches!(self, SyntaxError::Unclosed { .. })
+    }
+}
+
+impl Report {
+    pub fn is_corrupted(&self) -> bool {
+        self.errors.iter().any(SyntaxError::is_corrupting)
+    }
+
+    /// Returns the first illegal (or unknown) character of a corrupted line.
+    pub fn first_illegal(&self) -> Option<char> {
+        self.errors.iter().find_map(|error| match *error {
+            SyntaxError::Illegal { found, .. } | SyntaxError::Unknown { found, .. } => Some(found),
+            SyntaxError::Unclosed { .. } => None,
+        })
+    }
+}
+
+impl Language {
+    pub fn new(pairs: Vec<Pair>, completion_base: u64) -> Self {
+        Language {
+            pairs,
+            completion_base,
+        }
+    }
+
+    /// Builds a language from its pairs (e.g. "()[]"), with the error and completion
+    /// points of each one, and a completion base of one more than the number of pairs.
+    pub fn from_table(pairs: &str, error_points: &[u64], completion_points: &[u64]) -> Self {
+        let delimiters = pairs.chars().collect::<Vec<_>>();
+        assert_eq!(delimiters.len() % 2, 0, "unpaired delimiter in {}", pairs);
+        assert_eq!(delimiters.len() / 2, error_points.len());
+        assert_eq!(delimiters.len() / 2, completion_points.len());
+
+        let pairs = delimiters
+            .chunks(2)
+            .zip(error_points.iter().zip(completion_points))
+            .map(|(pair, (&error_points, &completion_points))| Pair {
+                open: pair[0],
+                close: pair[1],
+                error_points,
+                completion_points,
+            })
+            .collect::<Vec<_>>();
+        let completion_base = pairs.len() as u64 + 1;
+
+        Language::new(pairs, completion_base)
+    }
+
+    fn opened_by(&self, chr: char) -> Option<&Pair> {
+        self.pairs.iter().find(|pair| pair.open == chr)
+    }
+
+    fn closed_by(&self, chr: char) -> Option<&Pair> {
+        self.pairs.iter().find(|pair| pair.close == chr)
+    }
+
+    /// Returns the points scored by `chr` as an illegal character (0 if it isn't a delimiter).
+    pub fn error_score(&self, chr: char) -> u64 {
+        self.closed_by(chr).map_or(0, |pair| pair.error_points)
+    }
+
+    pub fn completion_score(&self, completion: &str) -> u64 {
+        completion.chars().fold(0, |score, chr| {
+            let points = self.closed_by(chr).map_or(0, |pair| pair.completion_points);
+            score * self.completion_base + points
+        })
+    }
+
+    /// Returns every error in `line`, and the delimiters that close the chunks left
+    /// open (innermost first).
+    fn errors(&self, line: &[char]) -> (Vec<SyntaxError>, String) {
+        let mut errors = vec![];
+        let mut open_chunks: Vec<(usize, &Pair)> = vec![];
+
+        for (column, &chr) in (1..).zip(line) {
+            if let Some(pair) = self.opened_by(chr) {
+                open_chunks.push((column, pair));
+            } else if self.closed_by(chr).is_some() {
+                match open_chunks.pop() {
+                    Some((_, pair)) if pair.close == chr => {}
+                    innermost => errors.push(SyntaxError::Illegal {
+                        column,
+                        found: chr,
+                        expected: innermost.map(|(_, pair)| pair.close),
+                    }),
+                }
+            } else {
+                errors.push(SyntaxError::Unknown { column, found: chr });
+            }
+        }
+
+        errors.extend(
+            open_chunks
+                .iter()
+                .map(|&(column, pair)| SyntaxError::Unclosed {
+                    column,
+                    open: pair.open,
+                }),
+        );
+        let completion = open_chunks
+            .iter()
+            .rev()
+            .map(|(_, pair)| pair.close)
+            .collect();
+
+        (errors, completion)
+    }
+
+    /// Returns the first single-character edit of the first corrupting error at `column`
+    /// after which `line` isn't corrupted anymore, trying to replace the character with
+    /// the `expected` one, to delete it, and to insert the `expected` one before it.
+    fn repair(&self, line: &[char], column: usize, expected: Option<char>) -> Option<Repair> {
+        let i = column - 1;
+        let mut candidates = expected
+            .map(|with| Repair::Replace { column, with })
+            .into_iter()
+            .chain([Repair::Delete { column }])
+            .chain(expected.map(|chr| Repair::Insert { column, chr }));
+
+        candidates.find(|repair| {
+            let mut repaired = line.to_vec();
+            match *repair {
+                Repair::Replace { with, .. } => repaired[i] = with,
+                Repair::Insert { chr, .. } => repaired.insert(i, chr),
+                Repair::Delete { .. } => {
+                    repaired.remove(i);
+                }
+            }
+
+            let (errors, _) = self.errors(&repaired);
+            !errors.iter().any(SyntaxError::is_corrupting)
+        })
+    }
+
+    pub fn check(&self, line: &str) -> Report {
+        let line = line.chars().collect::<Vec<_>>();
+        let (errors, completion) = self.errors(&line);
+
+        let repair = match errors.iter().find(|error| error.is_corrupting()) {
+            Some(&SyntaxError::Illegal {
+                column, expected, ..
+            }) => self.repair(&line, column, expected),
+            Some(&SyntaxError::Unknown { column, .. }) => self.repair(&line, column, None),
+            _ => None,
+        };
+        let completion = match errors.iter().any(SyntaxError::is_corrupting) {
+            true => None,
+            false => Some(completion),
+        };
+
+        Report {
+            errors,
+            completion,
+            repair,
+        }
+    }
+
+    /// Checks each line read from `r` as it's needed, so that large inputs don't have
+    /// to fit in memory.
+    pub fn check_lines<'a, R: BufRead + 'a>(&'a self, r: R) -> impl Iterator<Item = Report> + 'a {
+        r.lines().map(move |line| self.check(&line.unwrap()))
+    }
+}
@@ -0,0 +1,315 @@
+//! Hexagonal grids, in axial, cube, doubled and offset coordinates.
+//!
+//! Ref.: https://www.redblobgames.com/grids/hexagons/
+
+use std::{
+    collections::HashSet,
+    ops::{Add, Mul, Sub},
+};
+
+/// Axial coordinates, with `r` increasing "down" (i.e. south).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Axial {
+    pub q: i64,
+    pub r: i64,
+}
+
+/// Cube coordinates, where `q + r + s == 0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub q: i64,
+    pub r: i64,
+    pub s: i64,
+}
+
+/// Doubled coordinates, where `col + row` is always even, as the columns are doubled
+/// for pointy-top hexagons ("doubled width") and the rows for flat-top ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Doubled {
+    pub col: i64,
+    pub row: i64,
+}
+
+/// Offset coordinates, where odd rows are shoved right for pointy-top hexagons ("odd-r")
+/// and odd columns are shoved down for flat-top ones ("odd-q").
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Offset {
+    pub col: i64,
+    pub row: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    PointyTop,
+    FlatTop,
+}
+
+/// Axial offsets of the six neighbors of a hexagon, counterclockwise.
+pub const DIRECTIONS: [Axial; 6] = [
+    Axial { q: 1, r: 0 },
+    Axial { q: 1, r: -1 },
+    Axial { q: 0, r: -1 },
+    Axial { q: -1, r: 0 },
+    Axial { q: -1, r: 1 },
+    Axial { q: 0, r: 1 },
+];
+
+impl Orientation {
+    /// Names of each of the `DIRECTIONS`, which depend on how hexagons are laid out.
+    pub fn direction_names(self) -> [&'static str; 6] {
+        match self {
+            Orientation::PointyTop => ["e", "ne", "nw", "w", "sw", "se"],
+            Orientation::FlatTop => ["se", "ne", "n", "nw", "sw", "s"],
+        }
+    }
+
+    /// Parses a sequence of directions with no delimiters (e.g. "esenee"),
+    /// returning `None` if it isn't entirely made up of valid ones.
+    pub fn parse_directions(self, mut s: &str) -> Option<Vec<Axial>> {
+        let names = self.direction_names();
+        let mut directions = vec![];
+
+        while !s.is_empty() {
+            // Two-letter names must be tried first, since "ne" starts with "n".
+            let (name, direction) = names
+                .iter()
+                .zip(DIRECTIONS.iter())
+                .filter(|(name, _)| s.starts_with(*name))
+                .max_by_key(|(name, _)| name.len())?;
+            directions.push(*direction);
+            s = &s[name.len()..];
+        }
+
+        Some(directions)
+    }
+}
+
+impl Add for Axial {
+    type Output = Axial;
+    fn add(self, other: Axial) -> Axial {
+        Axial {
+            q: self.q + other.q,
+            r: self.r + other.r,
+        }
+    }
+}
+
+impl Sub for Axial {
+    type Output = Axial;
+    fn sub(self, other: Axial) -> Axial {
+        Axial {
+            q: self.q - other.q,
+            r: self.r - other.r,
+        }
+    }
+}
+
+impl Mul<i64> for Axial {
+    type Output = Axial;
+    fn mul(self, k: i64) -> Axial {
+        Axial {
+            q: self.q * k,
+            r: self.r * k,
+        }
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(hex: Axial) -> Self {
+        Cube {
+            q: hex.q,
+            r: hex.r,
+            s: -hex.q - hex.r,
+        }
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(cube: Cube) -> Self {
+        Axial {
+            q: cube.q,
+            r: cube.r,
+        }
+    }
+}
+
+impl Cube {
+    /// Rounds fractional cube coordinates to the nearest hexagon.
+    fn round(q: f64, r: f64, s: f64) -> Cube {
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+
+        // Fix the coordinate with the largest rounding error, so that they still sum to 0.
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+
+        Cube {
+            q: rq as i64,
+            r: rr as i64,
+            s: -(rq as i64) - rr as i64,
+        }
+    }
+}
+
+impl Axial {
+    pub fn to_doubled(self, orientation: Orientation) -> Doubled {
+        match orientation {
+            Orientation::PointyTop => Doubled {
+                col: 2 * self.q + self.r,
+                row: self.r,
+            },
+            Orientation::FlatTop => Doubled {
+                col: self.q,
+                row: 2 * self.r + self.q,
+            },
+        }
+    }
+
+    pub fn neighbors(self) -> impl Iterator<Item = Axial> {
+        DIRECTIONS.iter().map(move |&direction| self + direction)
+    }
+}
+
+/// Conversions and geometry that day 24 doesn't need, as part of a general hex-grid API.
+#[allow(dead_code)] // not used by any puzzle yet
+impl Axial {
+    pub fn from_doubled(hex: Doubled, orientation: Orientation) -> Axial {
+        match orientation {
+            Orientation::PointyTop => Axial {
+                q: (hex.col - hex.row) / 2,
+                r: hex.row,
+            },
+            Orientation::FlatTop => Axial {
+                q: hex.col,
+                r: (hex.row - hex.col) / 2,
+            },
+        }
+    }
+
+    pub fn to_offset(self, orientation: Orientation) -> Offset {
+        match orientation {
+            Orientation::PointyTop => Offset {
+                col: self.q + (self.r - (self.r & 1)) / 2,
+                row: self.r,
+            },
+            Orientation::FlatTop => Offset {
+                col: self.q,
+                row: self.r + (self.q - (self.q & 1)) / 2,
+            },
+        }
+    }
+
+    pub fn from_offset(hex: Offset, orientation: Orientation) -> Axial {
+        match orientation {
+            Orientation::PointyTop => Axial {
+                q: hex.col - (hex.row - (hex.row & 1)) / 2,
+                r: hex.row,
+            },
+            Orientation::FlatTop => Axial {
+                q: hex.col,
+                r: hex.row - (hex.col - (hex.col & 1)) / 2,
+            },
+        }
+    }
+
+    /// Returns the number of steps needed to go from `self` to `other`.
+    pub fn distance(self, other: Axial) -> i64 {
+        let Axial { q, r } = self - other;
+        (q.abs() + r.abs() + (q + r).abs()) / 2
+    }
+
+    /// Returns the hexagons which are exactly `radius` steps away from `self`.
+    pub fn ring(self, radius: i64) -> Vec<Axial> {
+        if radius == 0 {
+            return vec![self];
+        }
+
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+        let mut hex = self + DIRECTIONS[4] * radius;
+        for &direction in &DIRECTIONS {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex + direction;
+            }
+        }
+
+        ring
+    }
+
+    /// Returns the hexagons along a straight line from `self` to `other` (inclusive).
+    pub fn line_to(self, other: Axial) -> Vec<Axial> {
+        let n = self.distance(other);
+        if n == 0 {
+            return vec![self];
+        }
+
+        // Nudge the endpoints, so that points on the edge between two hexagons
+        // are consistently rounded towards the same side.
+        const EPSILON: f64 = 1e-6;
+        let (a, b) = (Cube::from(self), Cube::from(other));
+        let lerp = |x: i64, y: i64, t: f64| x as f64 + (y - x) as f64 * t;
+
+        (0..=n)
+            .map(|i| {
+                let t = i as f64 / n as f64;
+                Axial::from(Cube::round(
+                    lerp(a.q, b.q, t) + EPSILON,
+                    lerp(a.r, b.r, t) + EPSILON,
+                    lerp(a.s, b.s, t) - 2.0 * EPSILON,
+                ))
+            })
+            .collect()
+    }
+
+    /// Rotates `self` around `center` by 60 degrees clockwise `steps` times
+    /// (or counterclockwise, if negative).
+    pub fn rotate(self, center: Axial, steps: i64) -> Axial {
+        let mut cube = Cube::from(self - center);
+        for _ in 0..steps.rem_euclid(6) {
+            cube = Cube {
+                q: -cube.r,
+                r: -cube.s,
+                s: -cube.q,
+            };
+        }
+
+        center + Axial::from(cube)
+    }
+}
+
+/// Renders an ASCII map of the hexagons in `hexes` as '#' (and of the ones
+/// around them as '.'), laid out in doubled coordinates.
+pub fn render(hexes: &HashSet<Axial>, orientation: Orientation) -> String {
+    let doubled = hexes
+        .iter()
+        .map(|hex| hex.to_doubled(orientation))
+        .collect::<HashSet<_>>();
+
+    let (min_col, max_col) = (
+        doubled.iter().map(|hex| hex.col).min().unwrap_or(0),
+        doubled.iter().map(|hex| hex.col).max().unwrap_or(0),
+    );
+    let (min_row, max_row) = (
+        doubled.iter().map(|hex| hex.row).min().unwrap_or(0),
+        doubled.iter().map(|hex| hex.row).max().unwrap_or(0),
+    );
+
+    let mut map = String::new();
+    for row in min_row..=max_row {
+        for col in min_col..=max_col {
+            map.push(if (col + row) % 2 != 0 {
+                ' '
+            } else if doubled.contains(&Doubled { col, row }) {
+                '#'
+            } else {
+                '.'
+            });
+        }
+        map.push('\n');
+    }
+
+    map
+}
@@ -0,0 +1,158 @@
+//! Finding every set of `k` entries which add up to a target sum (the "k-sum" problem).
+
+use std::{cmp::Ordering, collections::HashMap, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Fix the smallest entries one at a time, and find the last two with two pointers
+    /// moving towards each other over the sorted entries, in O(n^(k-1)).
+    TwoPointer,
+    /// Find the sums of every half of the `k` entries, and join the two halves by
+    /// looking up the complement of each sum, in O(n^(k/2)) time and space.
+    MeetInTheMiddle,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "two-pointer" => Ok(Strategy::TwoPointer),
+            "meet-in-the-middle" => Ok(Strategy::MeetInTheMiddle),
+            _ => Err(format!("unknown strategy: {}", s)),
+        }
+    }
+}
+
+/// Returns every set of `k` distinct indices of `entries` (in increasing order) whose
+/// entries add up to `target`, in lexicographic order.
+pub fn k_sums(entries: &[i64], k: usize, target: i64, strategy: Strategy) -> Vec<Vec<usize>> {
+    let mut k_sums = match strategy {
+        _ if k == 0 => match target {
+            0 => vec![vec![]],
+            _ => vec![],
+        },
+        Strategy::TwoPointer => {
+            let mut sorted = (0..entries.len()).collect::<Vec<_>>();
+            sorted.sort_unstable_by_key(|&i| entries[i]);
+            let sorted = sorted
+                .into_iter()
+                .map(|i| (entries[i], i))
+                .collect::<Vec<_>>();
+
+            let mut k_sums = vec![];
+            two_pointer(&sorted, k, target, &mut vec![], &mut k_sums);
+            k_sums
+        }
+        Strategy::MeetInTheMiddle => meet_in_the_middle(entries, k, target),
+    };
+
+    for indices in &mut k_sums {
+        indices.sort_unstable();
+    }
+    k_sums.sort_unstable();
+
+    k_sums
+}
+
+/// Pushes to `k_sums` the indices of `chosen` along with those of every set of `k`
+/// of the `sorted` entries which add up to `target`.
+fn two_pointer(
+    sorted: &[(i64, usize)],
+    k: usize,
+    target: i64,
+    chosen: &mut Vec<usize>,
+    k_sums: &mut Vec<Vec<usize>>,
+) {
+    if k == 1 {
+        for &(_, i) in sorted.iter().filter(|&&(entry, _)| entry == target) {
+            k_sums.push([&chosen[..], &[i]].concat());
+        }
+        return;
+    }
+
+    if k > 2 {
+        for (first, &(entry, i)) in sorted.iter().enumerate() {
+            chosen.push(i);
+            two_pointer(&sorted[first + 1..], k - 1, target - entry, chosen, k_sums);
+            chosen.pop();
+        }
+        return;
+    }
+
+    let (mut lo, mut hi) = (0, sorted.len());
+    while lo + 1 < hi {
+        let (low, high) = (sorted[lo].0, sorted[hi - 1].0);
+        match (low + high).cmp(&target) {
+            Ordering::Less => lo += 1,
+            Ordering::Greater => hi -= 1,
+            Ordering::Equal if low == high => {
+                // Every pair of the (equal) entries in between is a match.
+                for a in lo..hi {
+                    for b in a + 1..hi {
+                        k_sums.push([&chosen[..], &[sorted[a].1, sorted[b].1]].concat());
+                    }
+                }
+                break;
+            }
+            Ordering::Equal => {
+                // Every low entry with every high entry, among those equal to them.
+                let lows = sorted[lo..hi]
+                    .iter()
+                    .take_while(|&&(e, _)| e == low)
+                    .count();
+                let highs = sorted[lo..hi]
+                    .iter()
+                    .rev()
+                    .take_while(|&&(e, _)| e == high)
+                    .count();
+                for &(_, a) in &sorted[lo..lo + lows] {
+                    for &(_, b) in &sorted[hi - highs..hi] {
+                        k_sums.push([&chosen[..], &[a, b]].concat());
+                    }
+                }
+                lo += lows;
+                hi -= highs;
+            }
+        }
+    }
+}
+
+/// Returns every set of `k` distinct indices below `len` (in increasing order).
+fn combinations(len: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut combinations = vec![vec![]];
+    for _ in 0..k {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination: Vec<usize>| {
+                let next = combination.last().map_or(0, |&last| last + 1);
+                (next..len).map(move |i| [&combination[..], &[i]].concat())
+            })
+            .collect();
+    }
+
+    combinations
+}
+
+fn meet_in_the_middle(entries: &[i64], k: usize, target: i64) -> Vec<Vec<usize>> {
+    let sum = |indices: &[usize]| indices.iter().map(|&i| entries[i]).sum::<i64>();
+
+    // Each set of indices is split into its `k / 2` lowest ones and the others.
+    let mut highs_by_sum: HashMap<i64, Vec<Vec<usize>>> = HashMap::new();
+    for highs in combinations(entries.len(), k - k / 2) {
+        highs_by_sum.entry(sum(&highs)).or_default().push(highs);
+    }
+
+    let mut k_sums = vec![];
+    for lows in combinations(entries.len(), k / 2) {
+        if let Some(all_highs) = highs_by_sum.get(&(target - sum(&lows))) {
+            for highs in all_highs {
+                if lows.last() < highs.first() {
+                    k_sums.push([&lows[..], &highs[..]].concat());
+                }
+            }
+        }
+    }
+
+    k_sums
+}
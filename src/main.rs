@@ -6,7 +6,16 @@ mod aoc2020;
 #[path = "./2021/mod.rs"]
 mod aoc2021;
 
+mod assignment;
+mod bigint;
+mod delimiters;
+mod hex;
+mod ksum;
+mod matrix;
+mod options;
+mod segment;
 mod solver;
+use options::Options;
 use solver::{
     Solver,
     SolverYear::{self, Aoc2020, Aoc2021},
@@ -15,6 +24,8 @@ use solver::{
 const YEAR: SolverYear = Aoc2021;
 
 fn main() {
+    let options = Options::from_args();
+
     let day: u8 = env::args()
         .nth(1)
         .and_then(|day| day.parse().ok())
@@ -22,29 +33,79 @@ fn main() {
         .expect("failed to parse day");
 
     match YEAR {
-        Aoc2020 => solve_aoc_2020(day),
-        Aoc2021 => solve_aoc_2021(day),
+        Aoc2020 => solve_aoc_2020(day, &options),
+        Aoc2021 => solve_aoc_2021(day, &options),
     }
 }
 
-fn solve_aoc_2021(day: u8) {
+fn solve_aoc_2021(day: u8, options: &Options) {
     match day {
         1 => aoc2021::day01::Day01 {}.solve(Aoc2021, day), // 1532, 1571
         2 => aoc2021::day02::Day02 {}.solve(Aoc2021, day), // 1459206, 1320534480
-        3 => aoc2021::day03::Day03 {}.solve(Aoc2021, day), // 2583164, 2784375
+        3 => aoc2021::day03::Day03 {
+            tie_most: options.value("--tie-most"),
+            tie_least: options.value("--tie-least"),
+            trace: options.flag("--trace"),
+        }
+        .solve(Aoc2021, day), // 2583164, 2784375
         4 => aoc2021::day04::Day04 {}.solve(Aoc2021, day), // 58412, 10030
-        5 => aoc2021::day05::Day05 {}.solve(Aoc2021, day), // 5124, 19771
-        6 => aoc2021::day06::Day06 {}.solve(Aoc2021, day), // 349549, 1589590444365
-        7 => aoc2021::day07::Day07 {}.solve(Aoc2021, day), // 336120, 96864235
-        8 => aoc2021::day08::Day08 {}.solve(Aoc2021, day), // 512, 1091165
-        9 => aoc2021::day09::Day09 {}.solve(Aoc2021, day), // 439, 900900
-        10 => aoc2021::day10::Day10 {}.solve(Aoc2021, day), // 392421, 2769449099
+        5 => aoc2021::day05::Day05 {
+            scale: options.value("--scale"),
+            cross_check: options.flag("--cross-check"),
+        }
+        .solve(Aoc2021, day), // 5124, 19771
+        6 => aoc2021::day06::Day06 {
+            days: options.value("--days"),
+            fast_forward: options.value("--fast-forward"),
+            modulo: options.value("--modulo"),
+        }
+        .solve(Aoc2021, day), // 349549, 1589590444365
+        7 => aoc2021::day07::Day07 {
+            cost: options.value("--cost"),
+        }
+        .solve(Aoc2021, day), // 336120, 96864235
+        8 => aoc2021::day08::Day08 {
+            glyphs: options.value("--glyphs"),
+            render: options.flag("--render"),
+        }
+        .solve(Aoc2021, day), // 512, 1091165
+        9 => aoc2021::day09::Day09 {
+            ridge: options.value("--ridge"),
+            diagonal: options.flag("--diagonal"),
+            render: options.flag("--render"),
+            ppm: options.value("--ppm"),
+        }
+        .solve(Aoc2021, day), // 439, 900900
+        10 => aoc2021::day10::Day10 {
+            pairs: options.value("--pairs"),
+            error_points: options.value("--error-points"),
+            completion_points: options.value("--completion-points"),
+            diagnose: options.flag("--diagnose"),
+        }
+        .solve(Aoc2021, day), // 392421, 2769449099
         11 => aoc2021::day11::Day11 {}.solve(Aoc2021, day), // 1681, 276
-        12 => aoc2021::day12::Day12 {}.solve(Aoc2021, day), // 3761, 99138
+        12 => aoc2021::day12::Day12 {
+            twice: options.value("--twice"),
+            paths: options.flag("--paths"),
+        }
+        .solve(Aoc2021, day), // 3761, 99138
         13 => aoc2021::day13::Day13 {}.solve(Aoc2021, day), // 687, FGKCKBZG
-        14 => aoc2021::day14::Day14 {}.solve(Aoc2021, day), // 3143, 4110215602456
+        14 => aoc2021::day14::Day14 {
+            steps: options.value("--steps"),
+            histogram: options.flag("--histogram"),
+            materialize: options.value("--materialize"),
+            fast_forward: options.value("--fast-forward"),
+            modulo: options.value("--modulo"),
+        }
+        .solve(Aoc2021, day), // 3143, 4110215602456
         15 => aoc2021::day15::Day15 {}.solve(Aoc2021, day), // 707, 2942
-        16 => aoc2021::day16::Day16 {}.solve(Aoc2021, day), // 913, 1510977819698
+        16 => aoc2021::day16::Day16 {
+            expression: options.flag("--expression"),
+            encode: options.flag("--encode"),
+            decode: options.value("--decode"),
+            encode_expression: options.value("--encode-expression"),
+        }
+        .solve(Aoc2021, day), // 913, 1510977819698
         17 => aoc2021::day17::Day17 {}.solve(Aoc2021, day), // 11175, 3540
         18 => aoc2021::day18::Day18 {}.solve(Aoc2021, day), // 3734, 4837
         // 19 => aoc2021::day19::Day19 {}.solve(Aoc2021, day), //
@@ -52,32 +113,95 @@ fn solve_aoc_2021(day: u8) {
     }
 }
 
-fn solve_aoc_2020(day: u8) {
+fn solve_aoc_2020(day: u8, options: &Options) {
     match day {
-        1 => aoc2020::day01::Day01 {}.solve(Aoc2020, day), // 691771, 232508760
+        1 => aoc2020::day01::Day01 {
+            target: options.value("--target"),
+            k: options.value("--k"),
+            strategy: options.value("--strategy"),
+        }
+        .solve(Aoc2020, day), // 691771, 232508760
         2 => aoc2020::day02::Day02 {}.solve(Aoc2020, day), // 546, 275
-        3 => aoc2020::day03::Day03 {}.solve(Aoc2020, day), // 209, 1574890240
-        4 => aoc2020::day04::Day04 {}.solve(Aoc2020, day), // 260, 153
-        5 => aoc2020::day05::Day05 {}.solve(Aoc2020, day), // 998, 676
-        6 => aoc2020::day06::Day06 {}.solve(Aoc2020, day), // 6249, 3103
-        7 => aoc2020::day07::Day07 {}.solve(Aoc2020, day), // 185, 89084
+        3 => aoc2020::day03::Day03 {
+            slopes: options.value("--slopes"),
+            search: options.value("--search"),
+            render: options.value("--render"),
+        }
+        .solve(Aoc2020, day), // 209, 1574890240
+        4 => aoc2020::day04::Day04 {
+            explain: options.flag("--explain"),
+            rules: options.value("--rules"),
+        }
+        .solve(Aoc2020, day), // 260, 153
+        5 => aoc2020::day05::Day05 {
+            rows: options.value("--rows"),
+            columns: options.value("--columns"),
+            map: options.flag("--map"),
+            encode: options.value("--encode"),
+            decode: options.value("--decode"),
+        }
+        .solve(Aoc2020, day), // 998, 676
+        6 => aoc2020::day06::Day06 {
+            alphabet: options.value("--alphabet"),
+            aggregation: options.value("--aggregation"),
+        }
+        .solve(Aoc2020, day), // 6249, 3103
+        7 => aoc2020::day07::Day07 {
+            bag: options.value("--bag"),
+            dot: options.value("--dot"),
+        }
+        .solve(Aoc2020, day), // 185, 89084
         8 => aoc2020::day08::Day08 {}.solve(Aoc2020, day), // 1489, 1539
-        9 => aoc2020::day09::Day09 {}.solve(Aoc2020, day), // 23278925, 4011064
+        9 => aoc2020::day09::Day09 {
+            preamble: options.value("--preamble"),
+            all_invalid: options.flag("--all-invalid"),
+        }
+        .solve(Aoc2020, day), // 23278925, 4011064
         10 => aoc2020::day10::Day10 {}.solve(Aoc2020, day), // 1917, 113387824750592
-        11 => aoc2020::day11::Day11 {}.solve(Aoc2020, day), // 2273, 2064
-        12 => aoc2020::day12::Day12 {}.solve(Aoc2020, day), // 508, 30761
+        11 => aoc2020::day11::Day11 {
+            occupy: options.value("--occupy"),
+            vacate: options.value("--vacate"),
+            in_sight: options.flag("--in-sight"),
+        }
+        .solve(Aoc2020, day), // 2273, 2064
+        12 => aoc2020::day12::Day12 {
+            log: options.flag("--log"),
+            svg: options.value("--svg"),
+        }
+        .solve(Aoc2020, day), // 508, 30761
         13 => aoc2020::day13::Day13 {}.solve(Aoc2020, day), // 3215, 1001569619313439
         14 => aoc2020::day14::Day14 {}.solve(Aoc2020, day), // 12408060320841, 4466434626828
-        15 => aoc2020::day15::Day15 {}.solve(Aoc2020, day), // 763, 1876406
+        15 => aoc2020::day15::Day15 {
+            prefix: options.value("--prefix"),
+            first: options.value("--first"),
+            stream: options.value("--stream"),
+            turns: options.value("--turns"),
+        }
+        .solve(Aoc2020, day), // 763, 1876406
         16 => aoc2020::day16::Day16 {}.solve(Aoc2020, day), // 19060, 953713095011
         17 => aoc2020::day17::Day17 {}.solve(Aoc2020, day), // 223, 1884
         18 => aoc2020::day18::Day18 {}.solve(Aoc2020, day), // 650217205854, 20394514442037
         19 => aoc2020::day19::Day19 {}.solve(Aoc2020, day), // 136, 256
         20 => aoc2020::day20::Day20 {}.solve(Aoc2020, day), // 84116744709593, 1957
         21 => aoc2020::day21::Day21 {}.solve(Aoc2020, day), // 1930, "spcqmzfg,rpf,dzqlq,pflk,bltrbvz,xbdh,spql,bltzkxx"
-        22 => aoc2020::day22::Day22 {}.solve(Aoc2020, day), // 35370, 36246
-        23 => aoc2020::day23::Day23 {}.solve(Aoc2020, day), // "45798623", 235551949822
-        24 => aoc2020::day24::Day24 {}.solve(Aoc2020, day), // 495, 4012
+        22 => aoc2020::day22::Day22 {
+            replay: options.flag("--replay"),
+            export: options.value("--export"),
+            stats: options.flag("--stats"),
+        }
+        .solve(Aoc2020, day), // 35370, 36246
+        23 => aoc2020::day23::Day23 {
+            cups: options.value("--cups"),
+            moves: options.value("--moves"),
+            pick_up: options.value("--pick-up"),
+            snapshot_every: options.value("--snapshot-every"),
+        }
+        .solve(Aoc2020, day), // "45798623", 235551949822
+        24 => aoc2020::day24::Day24 {
+            render: options.flag("--render"),
+            flat_top: options.flag("--flat-top"),
+        }
+        .solve(Aoc2020, day), // 495, 4012
         25 => aoc2020::day25::Day25 {}.solve(Aoc2020, day), // 4126980
         _ => eprintln!("That's all there is (no day {}).. see you next year!", day),
     }
@@ -0,0 +1,141 @@
+//! Square matrices over integers (exact or modular), used to fast-forward simulations
+//! whose steps are linear maps, as `M^n` takes O(log n) matrix multiplications.
+
+use crate::bigint::BigUint;
+use std::fmt::{self, Display};
+
+/// Integers which can be added and multiplied.
+pub trait Ring: Clone {
+    /// Returns `n`, of the same "kind" as `self` (e.g. with the same modulus).
+    fn of(&self, n: u64) -> Self;
+    fn plus(&self, other: &Self) -> Self;
+    fn times(&self, other: &Self) -> Self;
+}
+
+impl Ring for BigUint {
+    fn of(&self, n: u64) -> Self {
+        BigUint::from(n)
+    }
+    fn plus(&self, other: &Self) -> Self {
+        self.clone() + other
+    }
+    fn times(&self, other: &Self) -> Self {
+        self * other
+    }
+}
+
+/// Integer modulo `modulus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Modular {
+    value: u64,
+    modulus: u64,
+}
+
+impl Modular {
+    pub fn new(value: u64, modulus: u64) -> Self {
+        assert!(modulus > 0);
+        Modular {
+            value: value % modulus,
+            modulus,
+        }
+    }
+}
+
+impl Ring for Modular {
+    fn of(&self, n: u64) -> Self {
+        Modular::new(n, self.modulus)
+    }
+    fn plus(&self, other: &Self) -> Self {
+        let sum = (self.value as u128 + other.value as u128) % self.modulus as u128;
+        Modular::new(sum as u64, self.modulus)
+    }
+    fn times(&self, other: &Self) -> Self {
+        let product = (self.value as u128 * other.value as u128) % self.modulus as u128;
+        Modular::new(product as u64, self.modulus)
+    }
+}
+
+impl Display for Modular {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (mod {})", self.value, self.modulus)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Matrix<T> {
+    size: usize,
+    /// Row-major entries.
+    entries: Vec<T>,
+}
+
+impl<T: Ring> Matrix<T> {
+    /// Returns a `size` by `size` matrix where the entry at `(row, col)` is `entry(row, col)`.
+    pub fn from_fn(size: usize, entry: impl Fn(usize, usize) -> T) -> Self {
+        // Every entry has the same kind, which is given by the first one.
+        assert!(size > 0, "matrices can't be empty");
+        Matrix {
+            size,
+            entries: (0..size * size)
+                .map(|i| entry(i / size, i % size))
+                .collect(),
+        }
+    }
+
+    fn identity(&self) -> Self {
+        let (zero, one) = (self.entries[0].of(0), self.entries[0].of(1));
+        Matrix::from_fn(self.size, |row, col| {
+            if row == col {
+                one.clone()
+            } else {
+                zero.clone()
+            }
+        })
+    }
+
+    fn get(&self, row: usize, col: usize) -> &T {
+        &self.entries[row * self.size + col]
+    }
+
+    pub fn times(&self, other: &Matrix<T>) -> Matrix<T> {
+        assert_eq!(self.size, other.size);
+
+        let zero = self.entries[0].of(0);
+        Matrix::from_fn(self.size, |row, col| {
+            (0..self.size).fold(zero.clone(), |sum, k| {
+                sum.plus(&self.get(row, k).times(other.get(k, col)))
+            })
+        })
+    }
+
+    /// Returns `self` raised to the power of `exp`, by repeated squaring.
+    pub fn pow(&self, mut exp: u64) -> Matrix<T> {
+        let mut result = self.identity();
+        let mut base = self.clone();
+
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.times(&base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.times(&base);
+            }
+        }
+
+        result
+    }
+
+    /// Returns the product of `self` and the column vector `vector`.
+    pub fn apply(&self, vector: &[T]) -> Vec<T> {
+        assert_eq!(self.size, vector.len());
+
+        let zero = self.entries[0].of(0);
+        (0..self.size)
+            .map(|row| {
+                (0..self.size).fold(zero.clone(), |sum, k| {
+                    sum.plus(&self.get(row, k).times(&vector[k]))
+                })
+            })
+            .collect()
+    }
+}
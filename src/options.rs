@@ -0,0 +1,28 @@
+//! Command-line options given after the day, e.g. `cargo run -- 22 --replay`.
+
+use std::{env, str::FromStr};
+
+pub struct Options(Vec<String>);
+
+impl Options {
+    pub fn from_args() -> Self {
+        Options(env::args().skip(1).collect())
+    }
+
+    /// Returns true iff `name` was given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.0.iter().any(|arg| arg == name)
+    }
+
+    /// Returns the value given right after `name`, if any.
+    pub fn value<T: FromStr>(&self, name: &str) -> Option<T> {
+        let i = self.0.iter().position(|arg| arg == name)?;
+        let value = self.0.get(i + 1)?;
+
+        Some(
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid value for {}: {}", name, value)),
+        )
+    }
+}
@@ -0,0 +1,222 @@
+//! Line segments between integer points, and the (integer) lattice points on them.
+//!
+//! Overlaps are computed analytically, so their cost doesn't depend on how long the
+//! segments are, which lets coordinates go well beyond what could be rasterized.
+
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub start: Point,
+    pub end: Point,
+}
+
+/// A line through lattice points, as its primitive direction (normalized so that it
+/// points right, or up if vertical) and the cross product of that with its points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Line {
+    direction: (i64, i64),
+    offset: i128,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Segment {
+    pub fn is_horizontal(&self) -> bool {
+        self.start.y == self.end.y
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.start.x == self.end.x
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        let (dx, dy) = (self.end.x - self.start.x, self.end.y - self.start.y);
+        let (px, py) = (point.x - self.start.x, point.y - self.start.y);
+
+        dx as i128 * py as i128 == dy as i128 * px as i128
+            && (self.start.x.min(self.end.x)..=self.start.x.max(self.end.x)).contains(&point.x)
+            && (self.start.y.min(self.end.y)..=self.start.y.max(self.end.y)).contains(&point.y)
+    }
+
+    /// Returns the lattice points on the segment, from `start` to `end`.
+    pub fn lattice_points(&self) -> impl Iterator<Item = Point> {
+        let (dx, dy) = (self.end.x - self.start.x, self.end.y - self.start.y);
+        let steps = gcd(dx, dy);
+        let (step_x, step_y) = if steps == 0 {
+            (0, 0)
+        } else {
+            (dx / steps, dy / steps)
+        };
+
+        let start = self.start;
+        (0..=steps).map(move |i| Point {
+            x: start.x + i * step_x,
+            y: start.y + i * step_y,
+        })
+    }
+
+    fn line(&self) -> Line {
+        let (dx, dy) = (self.end.x - self.start.x, self.end.y - self.start.y);
+        // A single point is on a vertical line, like any other.
+        let (mut dx, mut dy) = match gcd(dx, dy) {
+            0 => (0, 1),
+            steps => (dx / steps, dy / steps),
+        };
+        if dx < 0 || (dx == 0 && dy < 0) {
+            (dx, dy) = (-dx, -dy);
+        }
+
+        Line {
+            direction: (dx, dy),
+            offset: dx as i128 * self.start.y as i128 - dy as i128 * self.start.x as i128,
+        }
+    }
+
+    /// Returns the lattice point where `self` crosses `other`, if they aren't parallel
+    /// (where a single point is parallel to nothing).
+    fn crossing(&self, other: &Segment) -> Option<Point> {
+        let cross = |(ax, ay): (i128, i128), (bx, by): (i128, i128)| ax * by - ay * bx;
+        let vector = |from: Point, to: Point| ((to.x - from.x) as i128, (to.y - from.y) as i128);
+
+        let (r, s) = (vector(self.start, self.end), vector(other.start, other.end));
+        if r == (0, 0) {
+            return other.contains(self.start).then_some(self.start);
+        } else if s == (0, 0) {
+            return self.contains(other.start).then_some(other.start);
+        }
+
+        let denominator = cross(r, s);
+        if denominator == 0 {
+            return None;
+        }
+
+        // The crossing is at `self.start + t * r` and `other.start + u * s`.
+        let between = vector(self.start, other.start);
+        let (mut t, mut u, mut denominator) = (cross(between, s), cross(between, r), denominator);
+        if denominator < 0 {
+            (t, u, denominator) = (-t, -u, -denominator);
+        }
+        if !(0..=denominator).contains(&t) || !(0..=denominator).contains(&u) {
+            return None;
+        }
+
+        let (x, y) = (
+            self.start.x as i128 * denominator + t * r.0,
+            self.start.y as i128 * denominator + t * r.1,
+        );
+        if x % denominator != 0 || y % denominator != 0 {
+            return None;
+        }
+
+        Some(Point {
+            x: (x / denominator) as i64,
+            y: (y / denominator) as i64,
+        })
+    }
+}
+
+/// Returns the number of lattice points on at least two of the `segments`.
+///
+/// Collinear segments are grouped by line, and their overlaps measured by sweeping
+/// along it. Crossings are found by sweeping a vertical line from left to right, only
+/// testing each segment against those that overlap it horizontally.
+pub fn count_overlaps(segments: &[Segment]) -> usize {
+    let lines = segments.iter().map(Segment::line).collect::<Vec<_>>();
+
+    let mut collinear: HashMap<Line, Vec<usize>> = HashMap::new();
+    for (i, &line) in lines.iter().enumerate() {
+        collinear.entry(line).or_default().push(i);
+    }
+
+    let mut overlaps = 0;
+    for (line, on_line) in &collinear {
+        // Points on the line are measured by their dot product with its direction,
+        // so consecutive lattice points are `spacing` apart.
+        let (dx, dy) = line.direction;
+        let spacing = (dx * dx + dy * dy).max(1) as i128;
+        let position = |p: Point| p.x as i128 * dx as i128 + p.y as i128 * dy as i128;
+
+        let mut events = on_line
+            .iter()
+            .flat_map(|&i| {
+                let (a, b) = (position(segments[i].start), position(segments[i].end));
+                [(a.min(b), 1), (a.max(b) + spacing, -1)]
+            })
+            .collect::<Vec<_>>();
+        events.sort_unstable();
+
+        let (mut covering, mut previous) = (0, 0);
+        for (position, change) in events {
+            if covering >= 2 {
+                overlaps += ((position - previous) / spacing) as usize;
+            }
+            covering += change;
+            previous = position;
+        }
+    }
+
+    let mut order = (0..segments.len()).collect::<Vec<_>>();
+    let min_x = |i: usize| segments[i].start.x.min(segments[i].end.x);
+    let max_x = |i: usize| segments[i].start.x.max(segments[i].end.x);
+    order.sort_unstable_by_key(|&i| min_x(i));
+
+    let mut crossings: HashMap<Point, Vec<usize>> = HashMap::new();
+    let mut active: Vec<usize> = vec![];
+    for i in order {
+        active.retain(|&j| max_x(j) >= min_x(i));
+        for &j in &active {
+            if let Some(point) = segments[i].crossing(&segments[j]) {
+                let through = crossings.entry(point).or_default();
+                through.extend([i, j]);
+            }
+        }
+        active.push(i);
+    }
+
+    // A crossing was already counted once for each line with (at least) two collinear
+    // segments through it, and must be counted exactly once overall.
+    let mut overlaps = overlaps as isize;
+    for through in crossings.values_mut() {
+        through.sort_unstable();
+        through.dedup();
+
+        let through_lines = through.iter().map(|&i| lines[i]).collect::<Vec<_>>();
+        let overlapping_lines = through_lines
+            .iter()
+            .enumerate()
+            .filter(|&(k, line)| {
+                !through_lines[..k].contains(line) && through_lines[k + 1..].contains(line)
+            })
+            .count();
+
+        overlaps += 1 - overlapping_lines as isize;
+    }
+
+    overlaps as usize
+}
+
+/// Returns the number of lattice points on at least two of the `segments`, by
+/// enumerating all of them (so it's only usable on small coordinates).
+pub fn count_overlaps_rasterized(segments: &[Segment]) -> usize {
+    let mut points = HashMap::<Point, usize>::new();
+    for segment in segments {
+        for point in segment.lattice_points() {
+            *points.entry(point).or_insert(0) += 1;
+        }
+    }
+
+    points.values().filter(|&&count| count > 1).count()
+}
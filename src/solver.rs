@@ -17,6 +17,9 @@ pub trait Solver {
     fn solve_part2(&self, input: &Self::Input) -> Self::Output2;
     fn parse_input<R: io::Read>(&self, r: R) -> Self::Input;
 
+    /// Prints any additional output, e.g. requested through command-line options.
+    fn report(&self, _input: &Self::Input) {}
+
     fn load_input<P: AsRef<Path>>(&self, p: P) -> io::Result<Self::Input> {
         let f = File::open(p)?;
 
@@ -35,5 +38,7 @@ pub trait Solver {
 
         println!("[Day {}] Answer 1: {}", day, self.solve_part1(&input));
         println!("[Day {}] Answer 2: {}", day, self.solve_part2(&input));
+
+        self.report(&input);
     }
 }